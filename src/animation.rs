@@ -0,0 +1,218 @@
+// src/animation.rs
+
+use crate::camera::Camera;
+use nalgebra::{Point3, Vector3 as Vec3};
+use std::fmt;
+
+// Camera pose at a point in time (seconds). `fov` is vertical, in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub time: f32,
+    pub position: Point3<f32>,
+    pub target: Point3<f32>,
+    pub fov: f32,
+}
+
+// Camera path through a list of keyframes sorted by time.
+// Position, target and FOV follow Catmull-Rom splines, so the camera passes
+// through every keyframe with a continuous velocity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+// Why a list of keyframes can't form a path; `index` is the offending keyframe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraPathError {
+    NonFinite { index: usize },
+    OutOfOrder { index: usize },
+}
+
+impl fmt::Display for CameraPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraPathError::NonFinite { index } => {
+                write!(f, "el keyframe {} tiene valores no finitos", index + 1)
+            }
+            CameraPathError::OutOfOrder { index } => {
+                write!(f, "el keyframe {} no está en orden de tiempo creciente", index + 1)
+            }
+        }
+    }
+}
+
+impl CameraPathError {
+    pub fn index(&self) -> usize {
+        match *self {
+            CameraPathError::NonFinite { index } | CameraPathError::OutOfOrder { index } => index,
+        }
+    }
+}
+
+impl std::error::Error for CameraPathError {}
+
+impl CameraPath {
+    // Keyframe times must be finite and strictly increasing, and poses finite
+    pub fn new(keyframes: Vec<Keyframe>) -> Result<Self, CameraPathError> {
+        for (index, key) in keyframes.iter().enumerate() {
+            let finite = key.time.is_finite()
+                && key.fov.is_finite()
+                && key.position.iter().chain(key.target.iter()).all(|c| c.is_finite());
+            if !finite {
+                return Err(CameraPathError::NonFinite { index });
+            }
+            if index > 0 && keyframes[index - 1].time >= key.time {
+                return Err(CameraPathError::OutOfOrder { index });
+            }
+        }
+        Ok(CameraPath { keyframes })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.keyframes.first().map_or(0.0, |k| k.time)
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    // Interpolated pose at `time`, held at the first/last keyframe outside the path
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let keys = &self.keyframes;
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if time <= first.time {
+            return Some(Keyframe { time, ..*first });
+        }
+        if time >= last.time {
+            return Some(Keyframe { time, ..*last });
+        }
+
+        // Segment [i, i + 1] containing `time`
+        let i = keys.partition_point(|k| k.time <= time) - 1;
+        let (k0, k1) = (&keys[i], &keys[i + 1]);
+        let dt = k1.time - k0.time;
+        let s = (time - k0.time) / dt;
+
+        let position = hermite(
+            k0.position.coords,
+            k1.position.coords,
+            self.tangent(i, |k| k.position.coords) * dt,
+            self.tangent(i + 1, |k| k.position.coords) * dt,
+            s,
+        );
+        let target = hermite(
+            k0.target.coords,
+            k1.target.coords,
+            self.tangent(i, |k| k.target.coords) * dt,
+            self.tangent(i + 1, |k| k.target.coords) * dt,
+            s,
+        );
+        let fov = hermite(
+            Vec3::repeat(k0.fov),
+            Vec3::repeat(k1.fov),
+            self.tangent(i, |k| Vec3::repeat(k.fov)) * dt,
+            self.tangent(i + 1, |k| Vec3::repeat(k.fov)) * dt,
+            s,
+        )
+        .x;
+
+        Some(Keyframe {
+            time,
+            position: Point3::from(position),
+            target: Point3::from(target),
+            fov,
+        })
+    }
+
+    // Moves `camera` to the pose at `time`; does nothing if the path is empty
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        if let Some(key) = self.sample(time) {
+            camera.look_at(key.position, key.target);
+            camera.set_fov(key.fov);
+        }
+    }
+
+    // Catmull-Rom tangent (per second) at keyframe `i`, one-sided at the ends
+    fn tangent<F: Fn(&Keyframe) -> Vec3<f32>>(&self, i: usize, value: F) -> Vec3<f32> {
+        let keys = &self.keyframes;
+        let prev = &keys[i.saturating_sub(1)];
+        let next = &keys[(i + 1).min(keys.len() - 1)];
+        if next.time > prev.time {
+            (value(next) - value(prev)) / (next.time - prev.time)
+        } else {
+            Vec3::zeros()
+        }
+    }
+}
+
+// Cubic Hermite interpolation between p0 and p1 with tangents m0 and m1, s in [0, 1]
+fn hermite(p0: Vec3<f32>, p1: Vec3<f32>, m0: Vec3<f32>, m1: Vec3<f32>, s: f32) -> Vec3<f32> {
+    let s2 = s * s;
+    let s3 = s2 * s;
+    p0 * (2.0 * s3 - 3.0 * s2 + 1.0) + m0 * (s3 - 2.0 * s2 + s) + p1 * (-2.0 * s3 + 3.0 * s2) + m1 * (s3 - s2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, x: f32) -> Keyframe {
+        Keyframe {
+            time,
+            position: Point3::new(x, 1.0, 5.0),
+            target: Point3::new(x, 0.0, 0.0),
+            fov: 1.0 + x * 0.1,
+        }
+    }
+
+    #[test]
+    fn passes_through_every_keyframe() {
+        let keys = vec![keyframe(0.0, 0.0), keyframe(1.0, 2.0), keyframe(3.0, -1.0), keyframe(4.0, 4.0)];
+        let path = CameraPath::new(keys.clone()).unwrap();
+        for key in &keys {
+            let sampled = path.sample(key.time).unwrap();
+            assert!((sampled.position - key.position).norm() < 1e-5);
+            assert!((sampled.target - key.target).norm() < 1e-5);
+            assert!((sampled.fov - key.fov).abs() < 1e-5);
+        }
+
+        // Between two keyframes the pose moves continuously
+        let before = path.sample(1.999).unwrap().position;
+        let after = path.sample(2.001).unwrap().position;
+        assert!((before - after).norm() < 1e-2);
+    }
+
+    #[test]
+    fn holds_the_end_poses_outside_the_path() {
+        let path = CameraPath::new(vec![keyframe(1.0, 0.0), keyframe(2.0, 3.0)]).unwrap();
+        assert_eq!(path.sample(-5.0).unwrap().position, Point3::new(0.0, 1.0, 5.0));
+        assert_eq!(path.sample(10.0).unwrap().position, Point3::new(3.0, 1.0, 5.0));
+        assert_eq!(path.sample(10.0).unwrap().time, 10.0);
+    }
+
+    #[test]
+    fn single_keyframe_and_empty_paths() {
+        let path = CameraPath::new(vec![keyframe(2.0, 1.0)]).unwrap();
+        for time in [0.0, 2.0, 7.0] {
+            assert_eq!(path.sample(time).unwrap().position, Point3::new(1.0, 1.0, 5.0));
+        }
+        assert!(CameraPath::default().sample(0.0).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_keyframes_with_their_index() {
+        let mut keys = vec![keyframe(0.0, 0.0), keyframe(1.0, 1.0), keyframe(1.0, 2.0)];
+        assert_eq!(CameraPath::new(keys.clone()), Err(CameraPathError::OutOfOrder { index: 2 }));
+        keys[1].target.x = f32::NAN;
+        assert_eq!(CameraPath::new(keys).unwrap_err().index(), 1);
+    }
+}
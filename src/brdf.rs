@@ -0,0 +1,233 @@
+// src/brdf.rs
+
+use crate::color::Color;
+use nalgebra::Vector3 as Vec3;
+use rand::Rng;
+use std::f32::consts::PI;
+
+// Cook-Torrance microfacet BRDF for the metal-roughness material model:
+// GGX (Trowbridge-Reitz) normal distribution, Smith-Schlick geometry term and
+// Fresnel-Schlick reflectance. `roughness` is perceptual (squared to get alpha).
+
+// Lowest roughness used in the distribution, so perfect mirrors keep a finite highlight
+const MIN_ROUGHNESS: f32 = 0.03;
+
+// Normal-incidence reflectance of a dielectric with index of refraction `ior`
+pub fn dielectric_f0(ior: f32) -> f32 {
+    let r = (ior - 1.0) / (ior + 1.0);
+    r * r
+}
+
+// Normal-incidence reflectance: the base color for metals, a grey F0 from the IOR
+// for dielectrics, blended by `metallic`
+pub fn base_reflectance(base_color: Color, metallic: f32, ior: f32) -> Color {
+    let f0 = dielectric_f0(ior);
+    Color::from_f32(f0, f0, f0).lerp(base_color, metallic)
+}
+
+// Schlick's approximation of the Fresnel reflectance at an angle with cosine `cos_theta`
+pub fn fresnel_schlick(cos_theta: f32, f0: Color) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0.lerp(Color::from_f32(1.0, 1.0, 1.0), weight)
+}
+
+// Fresnel-Schlick with the grazing reflectance capped by roughness, for the
+// reflection of the environment (rough surfaces don't turn into mirrors at grazing angles)
+pub fn fresnel_schlick_roughness(cos_theta: f32, f0: Color, roughness: f32) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    let grazing = 1.0 - roughness;
+    let max = Color::from_f32(f0.r.max(grazing), f0.g.max(grazing), f0.b.max(grazing));
+    f0.lerp(max, weight)
+}
+
+// Exact Fresnel reflectance of unpolarized light at the boundary between two
+// dielectrics, for a ray at `cos_i` to the normal and `eta` = n_incident / n_transmitted.
+// Returns 1 on total internal reflection.
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
+    if sin_t2 >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin_t2).sqrt();
+    let r_s = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_p = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_s * r_s + r_p * r_p) / 2.0
+}
+
+// GGX normal distribution for a half vector with cosine `n_dot_h` to the normal
+pub fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha = alpha(roughness);
+    let alpha2 = alpha * alpha;
+    let denom = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denom * denom)
+}
+
+// Smith shadowing-masking with the Schlick-GGX approximation for direct lighting
+pub fn smith_geometry(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let r = roughness.max(MIN_ROUGHNESS) + 1.0;
+    let k = r * r / 8.0;
+    let schlick = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    schlick(n_dot_v) * schlick(n_dot_l)
+}
+
+// Specular reflectance for light arriving from `light_dir` and leaving towards
+// `view_dir`, both pointing away from the surface, with normal-incidence
+// reflectance `f0`. The result still has to be multiplied by the incoming light
+// and cos(theta_l).
+pub fn specular(normal: &Vec3<f32>, view_dir: &Vec3<f32>, light_dir: &Vec3<f32>, f0: Color, roughness: f32) -> Color {
+    let n_dot_l = normal.dot(light_dir);
+    let n_dot_v = normal.dot(view_dir);
+    if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+        return Color::black();
+    }
+    let half_dir = (light_dir + view_dir).normalize();
+    let n_dot_h = normal.dot(&half_dir).max(0.0);
+    let v_dot_h = view_dir.dot(&half_dir).max(0.0);
+
+    fresnel_schlick(v_dot_h, f0)
+        * (ggx_distribution(n_dot_h, roughness) * smith_geometry(n_dot_v, n_dot_l, roughness)
+            / (4.0 * n_dot_v * n_dot_l))
+}
+
+// Share of the light that isn't reflected by the interface (`reflectance`) and
+// enters the surface; metals absorb it, dielectrics scatter or refract it
+pub fn transmitted(reflectance: Color, metallic: f32) -> Color {
+    Color::from_f32(1.0 - reflectance.r, 1.0 - reflectance.g, 1.0 - reflectance.b) * (1.0 - metallic)
+}
+
+// Direction of a glossy reflection: the view direction mirrored about a
+// microfacet normal drawn from the GGX distribution (a plain mirror when smooth)
+pub fn sample_reflection<R: Rng>(
+    normal: &Vec3<f32>,
+    view_dir: &Vec3<f32>,
+    roughness: f32,
+    rng: &mut R,
+) -> Vec3<f32> {
+    let mirror = |half: &Vec3<f32>| 2.0 * view_dir.dot(half) * half - view_dir;
+    if roughness <= MIN_ROUGHNESS {
+        return mirror(normal);
+    }
+
+    let alpha = alpha(roughness);
+    let (u1, u2): (f32, f32) = (rng.gen(), rng.gen());
+    let cos_theta = ((1.0 - u1) / (1.0 + (alpha * alpha - 1.0) * u1)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+
+    // Orthonormal basis around the normal
+    let helper = if normal.x.abs() > 0.9 { Vec3::y() } else { Vec3::x() };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    let half = (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta)
+        .normalize();
+
+    let direction = mirror(&half);
+    // Reflections below the surface fall back to the mirror direction
+    if direction.dot(normal) > 0.0 {
+        direction
+    } else {
+        mirror(normal)
+    }
+}
+
+fn alpha(roughness: f32) -> f32 {
+    let roughness = roughness.clamp(MIN_ROUGHNESS, 1.0);
+    roughness * roughness
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_incidence_reflectance_matches_f0() {
+        for ior in [1.33f32, 1.5, 2.4] {
+            let expected = ((ior - 1.0) / (ior + 1.0)).powi(2);
+            assert!((dielectric_f0(ior) - expected).abs() < 1e-6);
+            // Same reflectance entering and leaving the material
+            assert!((fresnel_dielectric(1.0, 1.0 / ior) - expected).abs() < 1e-6);
+            assert!((fresnel_dielectric(1.0, ior) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn total_internal_reflection_when_exiting() {
+        let ior = 1.5f32;
+        let critical = (1.0 / ior).asin();
+        let beyond = (critical + 0.05).cos();
+        let within = (critical - 0.05).cos();
+        assert_eq!(fresnel_dielectric(beyond, ior), 1.0);
+        assert!(fresnel_dielectric(within, ior) < 1.0);
+        // Entering from air never reflects everything
+        assert!(fresnel_dielectric(beyond, 1.0 / ior) < 1.0);
+    }
+
+    // Integral over the hemisphere of f(cos theta, phi), with `steps` samples per axis
+    fn integrate_hemisphere(steps: usize, f: impl Fn(f32, f32) -> f32) -> f32 {
+        let step = 1.0 / steps as f32;
+        let mut total = 0.0f64;
+        for i in 0..steps {
+            let cos_theta = (i as f32 + 0.5) * step;
+            for j in 0..steps {
+                let phi = (j as f32 + 0.5) * step * 2.0 * PI;
+                total += f64::from(f(cos_theta, phi));
+            }
+        }
+        (total * f64::from(step * step * 2.0 * PI)) as f32
+    }
+
+    #[test]
+    fn ggx_distribution_is_normalized() {
+        // The projected microfacet area covers the macro surface exactly once
+        for roughness in [0.3f32, 0.6, 1.0] {
+            // Rotationally symmetric: 2 pi times the integral over cos(theta_h)
+            let steps = 100_000;
+            let area = (0..steps)
+                .map(|i| {
+                    let n_dot_h = (i as f32 + 0.5) / steps as f32;
+                    ggx_distribution(n_dot_h, roughness) * n_dot_h
+                })
+                .sum::<f32>()
+                * 2.0
+                * PI
+                / steps as f32;
+            assert!((area - 1.0).abs() < 1e-2, "roughness {}: {}", roughness, area);
+        }
+    }
+
+    #[test]
+    fn white_furnace_reflects_no_more_than_it_receives() {
+        let normal = Vec3::z();
+        let white = Color::from_f32(1.0, 1.0, 1.0);
+        for metallic in [0.0f32, 1.0] {
+            for roughness in [0.3f32, 0.6, 1.0] {
+                for view_angle in [0.0f32, 45.0, 80.0] {
+                    let angle = view_angle.to_radians();
+                    let view_dir = Vec3::new(angle.sin(), 0.0, angle.cos());
+                    let f0 = base_reflectance(white, metallic, 1.5);
+                    let reflectance = fresnel_schlick_roughness(normal.dot(&view_dir), f0, roughness);
+                    let diffuse = (white * transmitted(reflectance, metallic)).r / PI;
+
+                    // Energy split between the environment reflection and the surface
+                    assert!(reflectance.r + transmitted(reflectance, metallic).r <= 1.0 + 1e-6);
+
+                    // Light from every direction, reflected towards the viewer
+                    let albedo = integrate_hemisphere(200, |cos_theta, phi| {
+                        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                        let light_dir = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                        (specular(&normal, &view_dir, &light_dir, f0, roughness).r + diffuse) * cos_theta
+                    });
+                    assert!(
+                        albedo <= 1.0 + 1e-2,
+                        "metallic {} roughness {} at {} degrees reflects {}",
+                        metallic,
+                        roughness,
+                        view_angle,
+                        albedo
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+// src/bvh.rs
+
+use crate::ray_intersect::{Intersect, RayIntersect};
+use nalgebra::Vector3 as Vec3;
+use std::ops::ControlFlow;
+
+const MAX_LEAF_SIZE: usize = 4;
+// Median splits keep the tree depth below log2(items), so the traversal stack
+// (at most one pending sibling per level) never outgrows this
+const MAX_STACK_DEPTH: usize = 64;
+
+// Axis-aligned bounding box used by the BVH
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3<f32>,
+    pub max: Vec3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3<f32>, max: Vec3<f32>) -> Self {
+        Aabb { min, max }
+    }
+
+    // Box with no extent, neutral element for `union`
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vec3::repeat(f32::INFINITY),
+            max: Vec3::repeat(f32::NEG_INFINITY),
+        }
+    }
+
+    // Box covering all of space, for primitives that can't be bounded
+    pub fn infinite() -> Self {
+        Aabb {
+            min: Vec3::repeat(f32::NEG_INFINITY),
+            max: Vec3::repeat(f32::INFINITY),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.iter().chain(self.max.iter()).all(|v| v.is_finite())
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    // Slab test against a ray given its inverse direction, limited to [0, t_max]
+    pub fn hit(&self, ray_origin: &Vec3<f32>, inv_direction: &Vec3<f32>, t_max: f32) -> bool {
+        let mut t_near = 0.0f32;
+        let mut t_far = t_max;
+        for axis in 0..3 {
+            let t1 = (self.min[axis] - ray_origin[axis]) * inv_direction[axis];
+            let t2 = (self.max[axis] - ray_origin[axis]) * inv_direction[axis];
+            // NaN (0 * inf) comparisons fall through and keep the current interval
+            t_near = t_near.max(t1.min(t2));
+            t_far = t_far.min(t1.max(t2));
+            if t_near > t_far {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+enum BvhNode {
+    Leaf { bounds: Aabb, start: usize, count: usize },
+    Interior { bounds: Aabb, left: usize, right: usize },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+// Bounding volume hierarchy over a list of objects.
+// Stores indices only, so the objects themselves can be re-borrowed (or their
+// materials changed) between frames as long as their geometry stays put.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<usize>,
+    // Objects without finite bounds are tested linearly on every ray
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build<T: RayIntersect>(objects: &[T]) -> Self {
+        let bounds: Vec<Aabb> = objects.iter().map(|object| object.bounding_box()).collect();
+        Bvh::from_bounds(&bounds)
+    }
+
+    // Builds the hierarchy from the bounds of each item; indices refer to `bounds`
+    pub fn from_bounds(bounds: &[Aabb]) -> Self {
+        let mut bvh = Bvh::default();
+        let mut bounded = Vec::new();
+        for (index, aabb) in bounds.iter().enumerate() {
+            if aabb.is_finite() {
+                bounded.push(index);
+            } else {
+                bvh.unbounded.push(index);
+            }
+        }
+
+        if !bounded.is_empty() {
+            bvh.build_node(bounds, &mut bounded, 0);
+            bvh.indices = bounded;
+        }
+        bvh
+    }
+
+    // Bounds of everything in the hierarchy (infinite if any item is unbounded)
+    pub fn bounds(&self) -> Aabb {
+        if !self.unbounded.is_empty() {
+            Aabb::infinite()
+        } else {
+            self.nodes.first().map_or(Aabb::empty(), |node| *node.bounds())
+        }
+    }
+    // Recursively splits `items` at the median centroid of its longest axis.
+    // `items` is reordered in place so every leaf owns a contiguous range.
+    fn build_node(&mut self, bounds: &[Aabb], items: &mut [usize], offset: usize) -> usize {
+        let node_bounds = items
+            .iter()
+            .fold(Aabb::empty(), |acc, &i| acc.union(&bounds[i]));
+        let node_index = self.nodes.len();
+
+        if items.len() <= MAX_LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf {
+                bounds: node_bounds,
+                start: offset,
+                count: items.len(),
+            });
+            return node_index;
+        }
+
+        let centroid_bounds = items.iter().fold(Aabb::empty(), |acc, &i| {
+            let c = bounds[i].centroid();
+            acc.union(&Aabb::new(c, c))
+        });
+        let extent = centroid_bounds.max - centroid_bounds.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |&a, &b| {
+            bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis])
+        });
+
+        // Placeholder, patched once both children exist
+        self.nodes.push(BvhNode::Leaf {
+            bounds: node_bounds,
+            start: 0,
+            count: 0,
+        });
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let left = self.build_node(bounds, left_items, offset);
+        let right = self.build_node(bounds, right_items, offset + mid);
+        self.nodes[node_index] = BvhNode::Interior {
+            bounds: node_bounds,
+            left,
+            right,
+        };
+        node_index
+    }
+
+    // Visits every item whose bounds the ray enters before `max_distance`.
+    // `hit` returns the distance of a hit on that item, if any; hits shrink the
+    // search range, and returning `ControlFlow::Break` stops the traversal.
+    pub fn traverse<F>(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>, max_distance: f32, mut hit: F)
+    where
+        F: FnMut(usize) -> ControlFlow<(), Option<f32>>,
+    {
+        let mut t_max = max_distance;
+        let mut visit = |index: usize, t_max: &mut f32| match hit(index) {
+            ControlFlow::Continue(Some(distance)) => {
+                *t_max = t_max.min(distance);
+                ControlFlow::Continue(())
+            }
+            ControlFlow::Continue(None) => ControlFlow::Continue(()),
+            ControlFlow::Break(()) => ControlFlow::Break(()),
+        };
+
+        for &index in &self.unbounded {
+            if visit(index, &mut t_max).is_break() {
+                return;
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inv_direction = ray_direction.map(|d| 1.0 / d);
+        // Fixed-size stack: traversal runs per ray (and per mesh), so no allocation
+        let mut stack = [0usize; MAX_STACK_DEPTH];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let node = &self.nodes[stack[len]];
+            if !node.bounds().hit(ray_origin, &inv_direction, t_max) {
+                continue;
+            }
+            match *node {
+                BvhNode::Leaf { start, count, .. } => {
+                    for &index in &self.indices[start..start + count] {
+                        if visit(index, &mut t_max).is_break() {
+                            return;
+                        }
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack[len] = right;
+                    stack[len + 1] = left;
+                    len += 2;
+                }
+            }
+        }
+    }
+
+    // Closest intersection along the ray, if any
+    pub fn intersect<T: RayIntersect>(
+        &self,
+        objects: &[T],
+        ray_origin: &Vec3<f32>,
+        ray_direction: &Vec3<f32>,
+    ) -> Option<Intersect> {
+        let mut closest: Option<Intersect> = None;
+        self.traverse(ray_origin, ray_direction, f32::INFINITY, |index| {
+            let hit = objects[index]
+                .ray_intersect(ray_origin, ray_direction)
+                .filter(|intersect| closest.as_ref().is_none_or(|c| intersect.distance < c.distance));
+            let distance = hit.as_ref().map(|intersect| intersect.distance);
+            if hit.is_some() {
+                closest = hit;
+            }
+            ControlFlow::Continue(distance)
+        });
+        closest
+    }
+
+    // True if anything blocks the ray before `max_distance` (shadow rays)
+    pub fn occluded<T: RayIntersect>(
+        &self,
+        objects: &[T],
+        ray_origin: &Vec3<f32>,
+        ray_direction: &Vec3<f32>,
+        max_distance: f32,
+    ) -> bool {
+        let mut occluded = false;
+        self.traverse(ray_origin, ray_direction, max_distance, |index| {
+            let blocks = objects[index]
+                .ray_intersect(ray_origin, ray_direction)
+                .is_some_and(|intersect| intersect.distance < max_distance);
+            if blocks {
+                occluded = true;
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(None)
+            }
+        });
+        occluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::transform::{compose, Transformed};
+    use nalgebra::Point3;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_point(rng: &mut StdRng, extent: f32) -> Vec3<f32> {
+        Vec3::new(
+            rng.gen_range(-extent..extent),
+            rng.gen_range(-extent..extent),
+            rng.gen_range(-extent..extent),
+        )
+    }
+
+    // Spheres and cubes, plain and transformed, plus unbounded vertical planes
+    fn random_scene(rng: &mut StdRng) -> Vec<Box<dyn RayIntersect>> {
+        let material = Material::new(Color::new(200, 200, 200), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        let mut objects: Vec<Box<dyn RayIntersect>> = Vec::new();
+        for _ in 0..40 {
+            let center = random_point(rng, 5.0);
+            let size = rng.gen_range(0.2..1.5);
+            objects.push(Box::new(Sphere::new(center, size, material.clone())));
+            objects.push(Box::new(Cube::new(Point3::from(random_point(rng, 5.0)), size, material.clone())));
+
+            let transform = compose(
+                random_point(rng, 5.0),
+                random_point(rng, 180.0),
+                Vec3::new(rng.gen_range(0.3..2.0), rng.gen_range(0.3..2.0), rng.gen_range(0.3..2.0)),
+            );
+            let sphere = Sphere::new(Vec3::zeros(), size, material.clone());
+            let cube = Cube::new(Point3::origin(), size, material.clone());
+            objects.push(Box::new(Transformed::new(sphere, transform).unwrap()));
+            objects.push(Box::new(Transformed::new(cube, transform).unwrap()));
+        }
+        for _ in 0..4 {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let normal = Vec3::new(angle.cos(), 0.0, angle.sin());
+            objects.push(Box::new(Plane::new(random_point(rng, 4.0), normal, material.clone())));
+            let turned = Plane::new(Vec3::zeros(), Vec3::x(), material.clone());
+            let transform = compose(random_point(rng, 2.0), Vec3::new(0.0, angle.to_degrees(), 0.0), Vec3::repeat(1.0));
+            objects.push(Box::new(Transformed::new(turned, transform).unwrap()));
+        }
+        objects
+    }
+
+    #[test]
+    fn traversal_matches_linear_scan() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let objects = random_scene(&mut rng);
+        let bvh = Bvh::build(&objects);
+        assert!(!bvh.unbounded.is_empty());
+
+        let rays = 2000;
+        let mut hits = 0;
+        for _ in 0..rays {
+            let origin = random_point(&mut rng, 8.0);
+            let direction = random_point(&mut rng, 1.0).try_normalize(1e-3).unwrap_or_else(Vec3::x);
+
+            let linear = objects
+                .iter()
+                .filter_map(|object| object.ray_intersect(&origin, &direction))
+                .map(|hit| hit.distance)
+                .fold(None, |closest: Option<f32>, distance| Some(closest.map_or(distance, |c| c.min(distance))));
+            let traversed = bvh.intersect(&objects, &origin, &direction).map(|hit| hit.distance);
+            assert_eq!(traversed, linear, "ray from {:?} along {:?}", origin, direction);
+            hits += usize::from(linear.is_some());
+
+            let max_distance = rng.gen_range(0.1..10.0);
+            let blocked = linear.is_some_and(|distance| distance < max_distance);
+            assert_eq!(
+                bvh.occluded(&objects, &origin, &direction, max_distance),
+                blocked,
+                "shadow ray from {:?} along {:?} up to {}",
+                origin,
+                direction,
+                max_distance
+            );
+        }
+        // Enough rays hit something for the comparison to mean anything
+        assert!(hits > rays / 4, "only {} of {} rays hit", hits, rays);
+    }
+
+    #[test]
+    fn coincident_items_fit_the_traversal_stack() {
+        // Identical boxes give the worst case for the split axis; every one
+        // must still be visited without overflowing the fixed-size stack
+        let count = 20_000;
+        let bounds = vec![Aabb::new(Vec3::repeat(-1.0), Vec3::repeat(1.0)); count];
+        let bvh = Bvh::from_bounds(&bounds);
+        let mut visited = 0;
+        bvh.traverse(&Vec3::new(0.0, 0.0, -5.0), &Vec3::z(), f32::INFINITY, |_| {
+            visited += 1;
+            ControlFlow::Continue(None)
+        });
+        assert_eq!(visited, count);
+    }
+}
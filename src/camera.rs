@@ -1,39 +1,178 @@
 extern crate nalgebra as na;
+use crate::sampling::sample_disk;
 use na::{Matrix4, Point3, Vector3};
 
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub position: Point3<f32>,
     pub target: Point3<f32>,
     pub up: Vector3<f32>,
     pub view_matrix: Matrix4<f32>,
 
+    // Projection: kind, vertical field of view (radians) and width / height of the image
+    // (the renderer overrides the aspect ratio with the framebuffer's)
+    pub projection: Projection,
+    pub fov: f32,
+    pub aspect_ratio: f32,
+
+    // Thin lens: aperture radius (0 = pinhole) and distance to the plane in focus.
+    // With `autofocus` the renderer focuses on whatever is under the image center.
+    pub aperture: f32,
+    pub focal_distance: f32,
+    pub autofocus: bool,
+
     // Spherical coordinates for orbiting
     pub azimuth_angle: f32,
     pub polar_angle: f32,
     pub radius: f32,
 }
 
+// How the interactive controls move the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,   // Orbit, zoom and pan around the target
+    FreeFly, // First-person: mouse-look and movement relative to the view
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::Orbit => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Orbit,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CameraMode::Orbit => "orbit",
+            CameraMode::FreeFly => "free-fly",
+        }
+    }
+}
+
+// How camera rays are laid out over the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,     // Pinhole (or thin lens) with the vertical `fov`
+    Orthographic,    // Parallel rays; `fov` sets the framing at the target distance
+    Equirectangular, // Full 360x180 degree panorama (use a 2:1 image)
+    Fisheye,         // Equidistant fisheye, 180 degrees across the image diagonal
+}
+
+impl Projection {
+    pub fn next(self) -> Self {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Fisheye,
+            Projection::Fisheye => Projection::Perspective,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic => "orthographic",
+            Projection::Equirectangular => "equirectangular",
+            Projection::Fisheye => "fisheye",
+        }
+    }
+
+    // Inverse of `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Projection::Perspective,
+            Projection::Orthographic,
+            Projection::Equirectangular,
+            Projection::Fisheye,
+        ]
+        .into_iter()
+        .find(|projection| projection.name() == name)
+    }
+}
+
+// Free-fly pitch limit, just short of straight up/down so `up` stays valid
+const MAX_PITCH: f32 = 1.55;
+
+pub const DEFAULT_FOV: f32 = std::f32::consts::FRAC_PI_3; // 60 degrees
+pub const MIN_FOV: f32 = 0.1745; // 10 degrees
+pub const MAX_FOV: f32 = 2.618; // 150 degrees
+
+// Range of focal distances reachable from the keyboard
+pub const MIN_FOCAL_DISTANCE: f32 = 0.01;
+pub const MAX_FOCAL_DISTANCE: f32 = 1e4;
+
 impl Camera {
     // Updated constructor
     pub fn new(position: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
         // Calculate the initial radius and angles for spherical coordinates
-        let radius = (target - position).magnitude();
-        let azimuth_angle = (position.z - target.z).atan2(position.x - target.x);
-        let polar_angle = (position.y - target.y).asin();
-
         let mut camera = Camera {
             position,
             target,
             up,
             view_matrix: Matrix4::identity(),
-            azimuth_angle,
-            polar_angle,
-            radius,
+            projection: Projection::Perspective,
+            fov: DEFAULT_FOV,
+            aspect_ratio: 1.0,
+            aperture: 0.0,
+            focal_distance: (target - position).magnitude(),
+            autofocus: false,
+            azimuth_angle: 0.0,
+            polar_angle: 0.0,
+            radius: 0.0,
         };
+        camera.update_spherical_from_cartesian();
         camera.update_view_matrix();
         camera
     }
 
+    // Changes the point the camera looks at and orbits around, keeping its position
+    pub fn set_target(&mut self, target: Point3<f32>) {
+        self.target = target;
+        self.update_spherical_from_cartesian();
+        self.update_view_matrix();
+    }
+
+    // Places the camera at `position` looking at `target`
+    pub fn look_at(&mut self, position: Point3<f32>, target: Point3<f32>) {
+        self.position = position;
+        self.set_target(target);
+    }
+
+    // Moves the camera and its target together in the view plane.
+    // The amounts are scaled by the distance to the target, so panning feels
+    // the same close up and far away.
+    pub fn pan(&mut self, delta_right: f32, delta_up: f32) {
+        let (_, right, up) = self.basis();
+        let offset = (right * delta_right + up * delta_up) * self.radius;
+        self.position += offset;
+        self.target += offset;
+        self.update_view_matrix();
+    }
+
+    // Free-fly mouse-look: turns the view direction around the camera position.
+    // The target stays in front of the camera at the same distance, so switching
+    // back to orbit mode orbits around what was being looked at.
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let forward = (self.target - self.position).normalize();
+        let yaw = forward.z.atan2(forward.x) + delta_yaw;
+        let pitch = (forward.y.clamp(-1.0, 1.0).asin() + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+        let forward = Vector3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin());
+        self.target = self.position + forward * self.radius;
+        self.update_spherical_from_cartesian();
+        self.update_view_matrix();
+    }
+
+    // Free-fly movement: `forward` along the view direction, `right` sideways
+    // and `up` along the global Y axis. Unlike orbiting, it is not kept above y = 0.
+    pub fn move_local(&mut self, forward: f32, right: f32, up: f32) {
+        let (forward_dir, right_dir, _) = self.basis();
+        let offset = forward_dir * forward + right_dir * right + Vector3::y() * up;
+        self.position += offset;
+        self.target += offset;
+        self.update_view_matrix();
+    }
+
     // Move left/right across the global X-axis
     pub fn move_right_global(&mut self, amount: f32) {
         // Global right vector is along the X-axis
@@ -61,9 +200,9 @@ impl Camera {
     // Update the spherical coordinates (radius, azimuth, polar) from the camera's position in Cartesian space
     fn update_spherical_from_cartesian(&mut self) {
         let direction = self.position - self.target;
-        self.radius = direction.magnitude();
+        self.radius = direction.magnitude().max(f32::EPSILON);
         self.azimuth_angle = direction.z.atan2(direction.x); // Azimuth angle
-        self.polar_angle = direction.y.asin().clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2); // Polar angle
+        self.polar_angle = (direction.y / self.radius).clamp(-1.0, 1.0).asin(); // Polar angle
     }
 
     // Orbit the camera based on azimuth (horizontal) and polar (vertical) changes
@@ -89,9 +228,6 @@ impl Camera {
 
     // Update the camera position based on spherical coordinates
     pub fn update_position(&mut self) {
-        // Lock del target de la cámara al origen (0,0,0)
-        self.target = Point3::new(0.0, 0.0, 0.0);
-    
         // Calcular la nueva posición usando coordenadas esféricas
         self.position.x =
             self.target.x + self.radius * self.polar_angle.cos() * self.azimuth_angle.cos();
@@ -110,12 +246,201 @@ impl Camera {
     
     // Update the view matrix
     pub fn update_view_matrix(&mut self) {
-        self.view_matrix = Matrix4::look_at_rh(&self.position, &self.target, &self.up);
+        let (forward, _, up) = self.basis();
+        self.view_matrix = Matrix4::look_at_rh(&self.position, &(self.position + forward), &up);
+    }
+
+    // Orthonormal (forward, right, up) frame of the view. When looking along `up`
+    // (e.g. a top-down plan view) `up` can't orient the image, so -Z is used
+    // instead: looking straight down, north (-Z) is at the top of the image.
+    fn basis(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+        let forward = (self.target - self.position)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(-Vector3::z());
+        let right = forward
+            .cross(&self.up)
+            .try_normalize(1e-4)
+            .or_else(|| forward.cross(&-Vector3::z()).try_normalize(1e-4))
+            .unwrap_or_else(Vector3::x);
+        (forward, right, right.cross(&forward))
+    }
+
+    // Sets the vertical field of view (radians), clamped to [MIN_FOV, MAX_FOV]
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Sets the focal distance, clamped to [MIN_FOCAL_DISTANCE, MAX_FOCAL_DISTANCE]
+    pub fn set_focal_distance(&mut self, distance: f32) {
+        if distance.is_finite() {
+            self.focal_distance = distance.clamp(MIN_FOCAL_DISTANCE, MAX_FOCAL_DISTANCE);
+        }
+    }
+
+    // Ray through a point of the image, as world-space (origin, direction).
+    // `u` and `v` are in [0, 1] from the top-left corner of the image. Everything
+    // that shoots camera rays (renderer, sky, picking) goes through here.
+    pub fn primary_ray(&self, u: f32, v: f32) -> (Vector3<f32>, Vector3<f32>) {
+        // Image point in [-1, 1], y up
+        let x = 2.0 * u - 1.0;
+        let y = 1.0 - 2.0 * v;
+        let half_height = (self.fov * 0.5).tan();
+
+        let local = match self.projection {
+            Projection::Perspective => Vector3::new(x * half_height * self.aspect_ratio, y * half_height, -1.0),
+            Projection::Orthographic => {
+                // Same framing as the perspective view at the target distance
+                let half_height = half_height * self.radius;
+                let right = self.transform_direction(&Vector3::x());
+                let up = self.transform_direction(&Vector3::y());
+                let origin = self.position.coords
+                    + right * (x * half_height * self.aspect_ratio)
+                    + up * (y * half_height);
+                return (origin, self.transform_direction(&-Vector3::z()));
+            }
+            Projection::Equirectangular => {
+                let longitude = x * std::f32::consts::PI;
+                let latitude = y * std::f32::consts::FRAC_PI_2;
+                Vector3::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                )
+            }
+            Projection::Fisheye => {
+                // Equidistant: the angle from the view axis grows linearly with the
+                // distance to the image center, reaching 90 degrees at the corners
+                let x = x * self.aspect_ratio;
+                let radius = (x * x + y * y).sqrt();
+                let half_diagonal = (self.aspect_ratio * self.aspect_ratio + 1.0).sqrt();
+                let theta = radius / half_diagonal * std::f32::consts::FRAC_PI_2;
+                if radius > 0.0 {
+                    let scale = theta.sin() / radius;
+                    Vector3::new(x * scale, y * scale, -theta.cos())
+                } else {
+                    -Vector3::z()
+                }
+            }
+        };
+        (self.position.coords, self.transform_direction(&local))
+    }
+
+    // Ray through a point of the image from a point on the lens, as (origin, direction).
+    // `lens` is a uniform sample in [0, 1)^2. Every lens ray through the same image
+    // point meets the pinhole ray on the focal plane, so only objects at
+    // `focal_distance` are sharp. Panoramic projections ignore the aperture.
+    pub fn lens_ray(&self, u: f32, v: f32, lens: (f32, f32)) -> (Vector3<f32>, Vector3<f32>) {
+        let (origin, direction) = self.primary_ray(u, v);
+        let thin_lens = matches!(self.projection, Projection::Perspective | Projection::Orthographic);
+        if self.aperture <= 0.0 || !thin_lens {
+            return (origin, direction);
+        }
+
+        // Distance along the ray to the focal plane, which faces the view direction
+        let (forward, right, up) = self.basis();
+        let focus = origin + direction * (self.focal_distance / direction.dot(&forward));
+
+        let (lens_x, lens_y) = sample_disk(lens.0, lens.1);
+        let origin = origin + (right * lens_x + up * lens_y) * self.aperture;
+        (origin, (focus - origin).normalize())
     }
 
-    // Transforms a direction vector using the view matrix of the camera
+    // Transforms a direction from camera space (looking down -Z) to world space.
+    // The view matrix goes from world to camera space, and its rotation part is
+    // orthonormal, so the inverse rotation is its transpose.
     pub fn transform_direction(&self, direction: &Vector3<f32>) -> Vector3<f32> {
-        let transformed_direction = self.view_matrix.transform_vector(direction);
-        transformed_direction.normalize()
+        let camera_to_world = self.view_matrix.fixed_view::<3, 3>(0, 0).transpose();
+        (camera_to_world * direction).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::framebuffer::Framebuffer;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::plane::Plane;
+    use crate::render::{RenderSettings, Renderer};
+    use crate::scene::Scene;
+    use crate::sphere::Sphere;
+
+    fn top_down() -> Camera {
+        Camera::new(Point3::new(0.0, 10.0, 0.0), Point3::origin(), Vector3::y())
+    }
+
+    #[test]
+    fn view_along_up_has_finite_basis() {
+        for projection in [Projection::Perspective, Projection::Orthographic] {
+            let mut camera = top_down();
+            camera.projection = projection;
+            assert!(camera.view_matrix.iter().all(|v| v.is_finite()));
+            for (u, v) in [(0.0, 0.0), (0.5, 0.5), (1.0, 1.0)] {
+                let (origin, direction) = camera.primary_ray(u, v);
+                assert!(origin.iter().chain(direction.iter()).all(|c| c.is_finite()));
+            }
+            let (_, center) = camera.primary_ray(0.5, 0.5);
+            assert!((center - -Vector3::y()).norm() < 1e-5);
+        }
+
+        // Looking straight down, the top of the image is north (-Z)
+        let mut camera = top_down();
+        camera.projection = Projection::Orthographic;
+        let (top, _) = camera.primary_ray(0.5, 0.0);
+        let (bottom, _) = camera.primary_ray(0.5, 1.0);
+        assert!(top.z < bottom.z);
+    }
+
+    #[test]
+    fn top_down_orthographic_render_sees_the_ground() {
+        let mut camera = top_down();
+        camera.projection = Projection::Orthographic;
+        let mut scene = Scene::new(camera);
+        let red = Material::new(Color::new(255, 0, 0), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        scene.add(Plane::new(Vector3::zeros(), Vector3::y(), red));
+        scene.add_light(Light::new(Vector3::new(0.0, 20.0, 0.0), Color::new(255, 255, 255), 1.0), None);
+
+        let mut framebuffer = Framebuffer::new(16, 16);
+        Renderer::new(RenderSettings::default()).render(&scene, &mut framebuffer);
+        let center = framebuffer.buffer[8 * 16 + 8];
+        let (r, g, b) = ((center >> 16) & 0xff, (center >> 8) & 0xff, center & 0xff);
+        assert!(r > 128 && g < 32 && b < 32, "center pixel {:06x}", center);
+    }
+
+    #[test]
+    fn render_follows_the_framebuffer_aspect_ratio() {
+        // The camera keeps its default 1:1 aspect ratio; the 2:1 image must not stretch the sphere
+        let camera = Camera::new(Point3::new(0.0, 0.0, 5.0), Point3::origin(), Vector3::y());
+        let mut scene = Scene::new(camera);
+        let red = Material::new(Color::new(255, 0, 0), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        scene.add(Sphere::new(Vector3::zeros(), 1.0, red));
+        scene.add_light(Light::new(Vector3::new(0.0, 0.0, 20.0), Color::new(255, 255, 255), 1.0), None);
+
+        let (width, height) = (64, 32);
+        let mut framebuffer = Framebuffer::new(width, height);
+        Renderer::new(RenderSettings::default()).render(&scene, &mut framebuffer);
+        let is_red = |pixel: u32| (pixel >> 16) & 0xff > 64 && (pixel >> 8) & 0xff < 32;
+        let across = (0..width).filter(|&x| is_red(framebuffer.buffer[height / 2 * width + x])).count();
+        let down = (0..height).filter(|&y| is_red(framebuffer.buffer[y * width + width / 2])).count();
+        assert!(across > 0 && across.abs_diff(down) <= 2, "{} pixels across, {} down", across, down);
+    }
+
+    #[test]
+    fn look_keeps_the_view_direction_when_radius_is_stale() {
+        let mut camera = Camera::new(Point3::new(0.0, 1.0, 5.0), Point3::origin(), Vector3::y());
+        let before = (camera.target - camera.position).normalize();
+        // `radius` is public and may not match the current distance to the target
+        camera.radius = 10.0;
+        camera.look(0.0, 0.0);
+        let after = (camera.target - camera.position).normalize();
+        assert!((after - before).norm() < 1e-5, "{:?} became {:?}", before, after);
+    }
+
+    #[test]
+    fn free_fly_moves_below_the_ground() {
+        let mut camera = Camera::new(Point3::new(0.0, 0.5, 0.0), Point3::new(0.0, -0.5, -1.0), Vector3::y());
+        camera.move_local(2.0, 0.0, 0.0);
+        assert!(camera.position.y < 0.0, "position {:?}", camera.position);
     }
 }
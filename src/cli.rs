@@ -0,0 +1,219 @@
+// src/cli.rs
+
+use tatooine::{OutputTransform, Projection, ToneMap};
+
+// Opciones de línea de comandos del binario
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub scene: String,          // Archivo de escena (TOML)
+    pub output: Option<String>, // Si está presente se renderiza sin ventana y se guarda la imagen
+    pub animate: Option<String>, // Carpeta donde guardar los frames del recorrido de la cámara
+    pub fps: f32,               // Frames por segundo de la animación
+    pub width: usize,
+    pub height: usize,
+    pub spp: u32,               // Muestras por píxel en modo sin ventana
+    pub fov: Option<f32>,       // Campo de visión vertical en grados (por defecto el de la escena)
+    pub projection: Option<Projection>, // Proyección de la cámara (por defecto la de la escena)
+    pub aperture: Option<f32>,  // Radio de la apertura de la lente (por defecto el de la escena)
+    pub focal_distance: Option<f32>,
+    pub autofocus: bool,        // Enfocar lo que haya en el centro de la imagen
+    pub threads: usize,         // 0 = todos los núcleos
+    pub output_transform: OutputTransform, // Exposición, curva de tono y sRGB iniciales
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            scene: "scenes/tatooine.toml".to_string(),
+            output: None,
+            animate: None,
+            fps: 24.0,
+            width: 800,
+            height: 600,
+            spp: 1,
+            fov: None,
+            projection: None,
+            aperture: None,
+            focal_distance: None,
+            autofocus: false,
+            threads: 0,
+            output_transform: OutputTransform::default(),
+        }
+    }
+}
+
+pub const USAGE: &str = "Uso: P3GC-Tatooine [opciones]
+
+Opciones:
+  --scene <archivo>    Archivo de escena a cargar (por defecto scenes/tatooine.toml)
+  --output <archivo>   Renderiza una imagen sin abrir ventana y la guarda (PNG, JPEG, ...)
+  --animate <carpeta>  Renderiza el recorrido de la cámara (keyframes de la escena) como
+                       PNG numerados en la carpeta, sin abrir ventana
+  --fps <n>            Frames por segundo de la animación (por defecto 24)
+  --width <px>         Ancho de la imagen (por defecto 800)
+  --height <px>        Alto de la imagen (por defecto 600)
+  --spp <n>            Muestras por píxel en modo sin ventana (por defecto 1)
+  --fov <grados>       Campo de visión vertical (por defecto el de la escena)
+  --projection <tipo>  perspective, orthographic, equirectangular o fisheye
+  --aperture <r>       Radio de la apertura para profundidad de campo, 0 = sin desenfoque
+  --focus <d>          Distancia de enfoque (por defecto la de la escena)
+  --autofocus          Enfoca el objeto que hay en el centro de la imagen
+  --threads <n>        Hilos de renderizado, 0 = todos los núcleos (por defecto 0)
+  --exposure <ev>      Exposición en pasos (EV) (por defecto 0)
+  --tonemap <curva>    none, reinhard, aces o filmic (por defecto none)
+  --no-srgb            No codifica la imagen final en sRGB (salida lineal)
+  --help               Muestra esta ayuda";
+
+impl Options {
+    // Interpreta los argumentos (sin incluir el nombre del programa)
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Falta el valor de {}", name))
+            };
+            match arg.as_str() {
+                "--scene" => options.scene = value(&arg)?,
+                "--output" | "-o" => options.output = Some(value(&arg)?),
+                "--animate" => options.animate = Some(value(&arg)?),
+                "--fps" => options.fps = parse_number(&arg, &value(&arg)?)?,
+                "--width" => options.width = parse_number(&arg, &value(&arg)?)?,
+                "--height" => options.height = parse_number(&arg, &value(&arg)?)?,
+                "--spp" => options.spp = parse_number(&arg, &value(&arg)?)?,
+                "--fov" => options.fov = Some(parse_number(&arg, &value(&arg)?)?),
+                "--projection" => {
+                    let name = value(&arg)?;
+                    options.projection = Some(
+                        Projection::from_name(&name)
+                            .ok_or_else(|| format!("Proyección desconocida: {}", name))?,
+                    );
+                }
+                "--aperture" => options.aperture = Some(parse_number(&arg, &value(&arg)?)?),
+                "--focus" => options.focal_distance = Some(parse_number(&arg, &value(&arg)?)?),
+                "--autofocus" => options.autofocus = true,
+                "--threads" => options.threads = parse_number(&arg, &value(&arg)?)?,
+                "--exposure" => options.output_transform.exposure = parse_number(&arg, &value(&arg)?)?,
+                "--tonemap" => {
+                    let name = value(&arg)?;
+                    options.output_transform.tone_map = ToneMap::from_name(&name)
+                        .ok_or_else(|| format!("Curva de tono desconocida: {}", name))?;
+                }
+                "--no-srgb" => options.output_transform.srgb = false,
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                _ => return Err(format!("Argumento desconocido: {}\n\n{}", arg, USAGE)),
+            }
+        }
+
+        if options.width == 0 || options.height == 0 {
+            return Err("El ancho y el alto deben ser mayores que 0".to_string());
+        }
+        if options.spp == 0 {
+            return Err("--spp debe ser mayor que 0".to_string());
+        }
+        if options.output.is_some() && options.animate.is_some() {
+            return Err("--output y --animate no se pueden usar a la vez".to_string());
+        }
+        if !options.fps.is_finite() || options.fps <= 0.0 {
+            return Err("--fps debe ser mayor que 0".to_string());
+        }
+        if options.fov.is_some_and(|fov| !(fov > 0.0 && fov < 180.0)) {
+            return Err("--fov debe estar entre 0 y 180 grados".to_string());
+        }
+        if options.aperture.is_some_and(|aperture| !(aperture.is_finite() && aperture >= 0.0)) {
+            return Err("--aperture debe ser un número finito no negativo".to_string());
+        }
+        if !options.output_transform.exposure.is_finite() {
+            return Err("--exposure debe ser un número finito".to_string());
+        }
+        if options.focal_distance.is_some_and(|distance| !(distance.is_finite() && distance > 0.0)) {
+            return Err("--focus debe ser un número finito mayor que 0".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Valor inválido para {}: {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_give_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let options = parse(&[
+            "--scene", "a.toml", "-o", "out.png", "--width", "320", "--height", "240", "--spp", "16",
+            "--fov", "45", "--projection", "fisheye", "--aperture", "0.1", "--focus", "3", "--autofocus",
+            "--threads", "2", "--exposure", "-1.5", "--tonemap", "aces", "--no-srgb",
+        ])
+        .unwrap();
+        assert_eq!(options.scene, "a.toml");
+        assert_eq!(options.output.as_deref(), Some("out.png"));
+        assert_eq!((options.width, options.height, options.spp), (320, 240, 16));
+        assert_eq!(options.fov, Some(45.0));
+        assert_eq!(options.projection, Some(Projection::Fisheye));
+        assert_eq!((options.aperture, options.focal_distance), (Some(0.1), Some(3.0)));
+        assert!(options.autofocus);
+        assert_eq!(options.threads, 2);
+        assert_eq!(
+            options.output_transform,
+            OutputTransform {
+                exposure: -1.5,
+                tone_map: ToneMap::Aces,
+                srgb: false,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_values_are_reported() {
+        assert_eq!(parse(&["--width"]), Err("Falta el valor de --width".to_string()));
+        assert_eq!(parse(&["--scene"]), Err("Falta el valor de --scene".to_string()));
+    }
+
+    #[test]
+    fn unknown_arguments_are_rejected() {
+        let error = parse(&["--size", "800x600"]).unwrap_err();
+        assert!(error.starts_with("Argumento desconocido: --size"), "{}", error);
+        assert!(parse(&["--help"]).unwrap_err().starts_with("Uso:"));
+    }
+
+    #[test]
+    fn invalid_numbers_are_rejected() {
+        let invalid: &[&[&str]] = &[
+            &["--width", "abc"],
+            &["--width", "-5"],
+            &["--width", "0"],
+            &["--spp", "0"],
+            &["--fps", "0"],
+            &["--fov", "200"],
+            &["--fov", "nan"],
+            &["--aperture", "-1"],
+            &["--aperture", "nan"],
+            &["--focus", "0"],
+            &["--focus", "inf"],
+            &["--exposure", "nan"],
+            &["--projection", "cubic"],
+            &["--tonemap", "gamma"],
+            &["--output", "a.png", "--animate", "frames"],
+        ];
+        for args in invalid {
+            assert!(parse(args).is_err(), "{:?} debería ser inválido", args);
+        }
+    }
+}
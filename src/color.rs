@@ -1,31 +1,75 @@
 // src/color.rs
 
-use std::ops::{Add, Mul};
+use std::ops::{Add, AddAssign, Mul};
 use nalgebra::Vector3;
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+
+// Radiancia RGB lineal en punto flotante. 1.0 corresponde a 255 en 8 bits, pero
+// los valores no se recortan hasta escribirse en el framebuffer (`to_hex`).
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
 }
 
 impl Color {
+    // Construye un color a partir de componentes de 8 bits en sRGB (como los de
+    // un selector de color o una imagen), decodificándolos a lineal
     pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Color {
+            r: srgb_decode(r),
+            g: srgb_decode(g),
+            b: srgb_decode(b),
+        }
+    }
+    // Construye un color a partir de componentes de 8 bits que ya son lineales
+    pub fn from_linear_u8(r: u8, g: u8, b: u8) -> Self {
+        Color {
+            r: r as f32 / 255.0,
+            g: g as f32 / 255.0,
+            b: b as f32 / 255.0,
+        }
+    }
+    // Construye un color directamente a partir de componentes lineales
+    pub fn from_f32(r: f32, g: f32, b: f32) -> Self {
         Color { r, g, b }
     }
     pub fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0 }
+    }
+    pub fn is_black(&self) -> bool {
+        self.r == 0.0 && self.g == 0.0 && self.b == 0.0
+    }
+    // Componente más intensa, para estimar cuánto aporta un color como peso
+    pub fn max_channel(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
     }
-    // Método para convertir el color a un valor hexadecimal (RGB)
-    pub fn to_hex(&self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    // Interpolación lineal entre `self` (t = 0) y `other` (t = 1)
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        self * (1.0 - t) + other * t
+    }
+    // Método para convertir el color a un valor hexadecimal (RGB), recortando a [0, 255]
+    pub fn to_hex(self) -> u32 {
+        let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (to_byte(self.r) << 16) | (to_byte(self.g) << 8) | to_byte(self.b)
     }
     pub fn from_vec(normal: Vector3<f32>) -> Self {
-        // Convertimos de [-1, 1] a [0, 255] para cada componente
-        let r = ((normal.x + 1.0) * 0.5 * 255.0) as u8;
-        let g = ((normal.y + 1.0) * 0.5 * 255.0) as u8;
-        let b = ((normal.z + 1.0) * 0.5 * 255.0) as u8;
-        Color::new(r, g, b)
+        // Convertimos de [-1, 1] a [0, 1] para cada componente
+        Color::from_f32(
+            (normal.x + 1.0) * 0.5,
+            (normal.y + 1.0) * 0.5,
+            (normal.z + 1.0) * 0.5,
+        )
+    }
+}
+
+// Decodificación sRGB de un componente de 8 bits a un valor lineal en [0, 1]
+fn srgb_decode(byte: u8) -> f32 {
+    let x = byte as f32 / 255.0;
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
     }
 }
 
@@ -35,22 +79,41 @@ impl Add for Color {
 
     fn add(self, other: Color) -> Color {
         Color {
-            r: (self.r as u16 + other.r as u16).min(255) as u8,
-            g: (self.g as u16 + other.g as u16).min(255) as u8,
-            b: (self.b as u16 + other.b as u16).min(255) as u8,
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
         }
     }
 }
 
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
 // Implementación del trait Mul<f32> para Color
 impl Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, scalar: f32) -> Color {
         Color {
-            r: ((self.r as f32) * scalar).min(255.0) as u8,
-            g: ((self.g as f32) * scalar).min(255.0) as u8,
-            b: ((self.b as f32) * scalar).min(255.0) as u8,
+            r: self.r * scalar,
+            g: self.g * scalar,
+            b: self.b * scalar,
+        }
+    }
+}
+
+// Multiplicación componente a componente (modulación de un color por otro)
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
         }
     }
 }
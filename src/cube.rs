@@ -1,3 +1,4 @@
+use crate::bvh::Aabb;
 use crate::material::Material;
 use crate::ray_intersect::{Intersect, RayIntersect};
 use nalgebra::{Point3, Vector3 as Vec3};
@@ -43,6 +44,15 @@ impl Cube {
         };
         (u.fract(), v.fract()) // Ensure the coordinates are between 0 and 1 (texture wrapping)
     }
+
+    // Directions in which u and v grow on the faces perpendicular to `axis` (see `get_uv`)
+    fn face_tangents(axis: usize) -> (Vec3<f32>, Vec3<f32>) {
+        match axis {
+            0 => (Vec3::z(), Vec3::y()),
+            1 => (Vec3::x(), Vec3::z()),
+            _ => (Vec3::x(), Vec3::y()),
+        }
+    }
 }
 
 // Implementación del trait RayIntersect para la estructura Cube
@@ -90,25 +100,33 @@ impl RayIntersect for Cube {
 
         let t = if tmin < 0.0 { tmax } else { tmin };
         let hit_point = ray_origin + ray_direction * t;
-        let normal = (hit_point - self.center.coords).normalize();
 
-        // Calculate UV coordinates for cube faces
-        let (u, v) = self.get_uv(&hit_point, &normal);
+        // Face normal: the axis along which the hit point is farthest from the center
+        let local = (hit_point - self.center.coords) / half_size;
+        let axis = local.iamax();
+        let mut normal = Vec3::zeros();
+        normal[axis] = local[axis].signum();
 
-        // Get texture color if available
-        let color = if let Some(texture) = &self.material.texture {
-            let u_pixel = (u * (texture.width - 1) as f32).round() as usize;
-            let v_pixel = (v * (texture.height - 1) as f32).round() as usize;
-            texture.as_ref().get_color(u_pixel, v_pixel) // Dereference Arc to get the inner Texture
-        } else {
-            self.material.color
-        };
-        
+        // Calculate UV coordinates for cube faces; the renderer samples the texture
+        let uv = self.get_uv(&hit_point, &normal);
+        let (tangent, bitangent) = Cube::face_tangents(axis);
+        Some(
+            Intersect::new(hit_point, normal, t, self.material.clone())
+                .with_uv(uv, 1.0 / self.size)
+                .with_tangents(tangent, bitangent),
+        )
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
 
-        // Return the intersect object with the texture color if available
-        Some(Intersect::new(hit_point, normal, t, Material {
-            color, // Use the color obtained from the texture or default material color
-            ..self.material.clone()
-        }))
+    fn bounding_box(&self) -> Aabb {
+        let half_size = Vec3::new(self.size / 2.0, self.size / 2.0, self.size / 2.0);
+        Aabb::new(self.center.coords - half_size, self.center.coords + half_size)
     }
 }
@@ -1,6 +1,7 @@
 // src/framebuffer.rs
 
-use crate::color::Color;
+use image::{ImageResult, Rgb, RgbImage};
+use std::path::Path;
 
 pub struct Framebuffer {
     pub width: usize,
@@ -31,9 +32,12 @@ impl Framebuffer {
         }
     }
 
-    // Método para dibujar un punto en el framebuffer
-    pub fn point(&mut self, x: usize, y: usize) {
-        // Este método ya no es necesario si usamos set_pixel directamente
-        // Puedes eliminarlo si no se usa en otros lugares
+    // Guarda el contenido del framebuffer como imagen; el formato se deduce de la extensión
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ImageResult<()> {
+        let image = RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = self.buffer[y as usize * self.width + x as usize];
+            Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+        });
+        image.save(path)
     }
 }
@@ -0,0 +1,58 @@
+// src/lib.rs
+//
+// Raytracer de Tatooine como biblioteca. El visor interactivo (src/main.rs) es un
+// binario delgado sobre esta API:
+//
+//     let scene = Scene::load("scenes/tatooine.toml")?;
+//     let mut framebuffer = Framebuffer::new(800, 600);
+//     Renderer::new(RenderSettings::default()).render(&scene, &mut framebuffer);
+//     framebuffer.save("out.png")?;
+
+pub mod animation;
+pub mod brdf;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod cube;
+pub mod framebuffer;
+pub mod light;
+pub mod material;
+pub mod mesh;
+pub mod plane;
+pub mod ray_intersect;
+pub mod render;
+pub mod sampling;
+pub mod scene;
+pub mod scene_file;
+pub mod skybox;
+pub mod sphere;
+pub mod texture;
+pub mod texture_registry;
+pub mod tonemap;
+pub mod transform;
+pub mod triangle;
+pub mod watch;
+
+pub use crate::animation::{CameraPath, CameraPathError, Keyframe};
+pub use crate::bvh::{Aabb, Bvh};
+pub use crate::camera::{Camera, CameraMode, Projection};
+pub use crate::color::Color;
+pub use crate::cube::Cube;
+pub use crate::framebuffer::Framebuffer;
+pub use crate::light::Light;
+pub use crate::material::{Material, NormalMapConvention, ShadingModel};
+pub use crate::mesh::{load_obj, load_obj_with_textures, Mesh, ObjError};
+pub use crate::plane::Plane;
+pub use crate::ray_intersect::{Intersect, RayIntersect};
+pub use crate::render::{RenderSettings, Renderer};
+pub use crate::sampling::{ReconstructionFilter, SamplePattern, SamplingSettings};
+pub use crate::scene::Scene;
+pub use crate::scene_file::SceneError;
+pub use crate::skybox::Skybox;
+pub use crate::sphere::Sphere;
+pub use crate::texture::{ColorSpace, Texture, TextureError, TextureFilter, WrapMode};
+pub use crate::texture_registry::{FallbackTexture, TextureRegistry, TextureUsage};
+pub use crate::tonemap::{OutputTransform, ToneMap};
+pub use crate::transform::Transformed;
+pub use crate::triangle::Triangle;
+pub use crate::watch::FileWatcher;
@@ -1,173 +1,121 @@
 // src/main.rs
+//
+// Visor interactivo (y modo sin ventana) sobre la biblioteca `tatooine`.
 
-mod camera;
-mod color;
-mod cube;
-mod framebuffer;
-mod light;
-mod material;
-mod plane;
-mod ray_intersect;
-mod render;
-mod skybox;
-mod sphere;
-mod texture;
-
-extern crate image;
-use crate::camera::Camera;
-use crate::color::Color;
-use crate::cube::Cube;
-use crate::framebuffer::Framebuffer;
-use crate::light::Light;
-use crate::material::Material;
-use crate::plane::Plane;
-use crate::render::render;
-use crate::skybox::Skybox;
-use crate::sphere::Sphere;
-use crate::texture::Texture;
+mod cli;
+
+use crate::cli::Options;
 use minifb::{Key, KeyRepeat, MouseButton, Window, WindowOptions};
 use nalgebra::Point3;
-use nalgebra::Vector3 as Vec3;
-use once_cell::sync::Lazy;
-use std::sync::Arc;
+use std::path::Path;
 use std::time::{Duration, Instant};
-use nalgebra::Rotation3;
-
-static SANDSTONE_TEXTURE: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/sandstone_normal.png")));
-static CLAY_TEXTURE: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/mud_bricks.png")));
-static METAL_TEXTURE: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/tuff_bricks.png")));
-static RUSTED_METAL_TEXTURE: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/red_sandstone_carved.png")));
-static SAND_TEXTURE: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/sand.png")));
-static SAND_NORMAL_MAP: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/rocky-dunes1_normal-ogl.png")));
+use tatooine::render::configure_threads;
+use tatooine::{
+    CameraMode, FileWatcher, Framebuffer, RenderSettings, Renderer, SamplePattern, SamplingSettings,
+    Scene, TextureRegistry,
+};
+
+// Ajustes de los renders sin ventana
+fn headless_settings(options: &Options) -> RenderSettings {
+    RenderSettings {
+        output: options.output_transform,
+        sampling: SamplingSettings {
+            samples: options.spp,
+            // Halton respeta el número exacto de muestras (los patrones de rejilla lo redondean a un cuadrado)
+            pattern: SamplePattern::Halton,
+            ..SamplingSettings::default()
+        },
+    }
+}
 
+fn print_warnings(scene: &Scene) {
+    for warning in &scene.warnings {
+        eprintln!("Aviso: {}", warning);
+    }
+}
 
 fn main() {
-    // Inicialización del framebuffer y skybox
-    let mut framebuffer = Framebuffer::new(800, 600); // Puedes ajustar la resolución
-    let mut skybox = Skybox::new();
-
-    // Define el objetivo alrededor del cual orbitar (por ejemplo, el centro de una estructura)
-    let target = Point3::new(0.0, 0.5, 0.0);
-
-    // Inicializa la cámara con posición, objetivo y vector up
-    let mut camera = Camera::new(
-        Point3::new(0.0, 1.0, 5.0), // Posición de la cámara
-        target,                     // Objetivo fijo (centro)
-        Vec3::new(0.0, 1.0, 0.0),   // Vector up
-    );
-    let sandstone_material = Material::new_with_texture(
-        Color::new(205, 170, 125),       // Default sandstone color
-        [0.5, 0.2, 0.01, 0.0],           // Albedo
-        5.0,                             // Specular
-        1.0,                             // Refractive index
-        Some(SANDSTONE_TEXTURE.clone()), // Use the sandstone texture
-        None,
-    );
-    let sand_material = Material::new_with_texture(
-        Color::new(205, 170, 125),       // Default sandstone color
-        [0.8, 0.2, 0.01, 0.0],           // Albedo
-        5.0,                             // Specular
-        1.0,                             // Refractive index
-        Some(SAND_TEXTURE.clone()),      // Use the sandstone texture
-        Some(SAND_NORMAL_MAP.clone()),   // Use the normal map for bumps
-    );
-    let clay_material = Material::new_with_texture(
-        Color::new(160, 82, 45),    // Default clay color
-        [0.7, 0.1, 0.1, 0.0],       // Albedo
-        15.0,                       // Specular
-        1.0,                        // Refractive index
-        Some(CLAY_TEXTURE.clone()), // Use the clay texture
-        None,
-
-    );
-
-    let metal_material = Material::new_with_texture(
-        Color::new(192, 192, 192),   // Default metal color
-        [0.5, 0.1, 0.1, 0.0],        // Albedo
-        250.0,                       // Specular
-        1.0,                         // Refractive index
-        Some(METAL_TEXTURE.clone()), // Use the metal texture
-        None,
-
-    );
-
-    let rusted_metal_material = Material::new_with_texture(
-        Color::new(139, 69, 19),            // Default rusted metal color
-        [0.5, 0.4, 0.1, 0.0],               // Albedo
-        100.0,                              // Specular
-        1.0,                                // Refractive index
-        Some(RUSTED_METAL_TEXTURE.clone()), // Use the rusted metal texture
-        None,
-    );
-
-    // Definir materiales para los soles
-    let day_sun_material = Material::yellow_sun();
-    let night_sun1_material = Material::red_giant();
-    let night_sun2_material = Material::yellow_sun(); // Puedes definir otro material si lo prefieres
-
-    // Inicializa los soles con el material de día
-    let mut suns = vec![
-        Sphere {
-            center: Vec3::new(1.0, 12.0, -6.0), // Posición del primer sol
-            radius: 1.0,
-            material: day_sun_material.clone(),
-        },
-        Sphere {
-            center: Vec3::new(6.0, 8.0, -7.5), // Posición del segundo sol
-            radius: 0.7,
-            material: day_sun_material.clone(),
-        },
-    ];
-
-    let fill_light = Light::new(
-        Vec3::new(-10.0, 5.0, 10.0), // Posición de la luz de relleno, ajusta según tu escena
-        Color::new(219, 153, 90),   // Color blanco o un color tenue para la luz
-        2.0,                         // Intensidad baja para que no sea tan dominante
-    );
-
-    let mut lights = vec![
-        // Luz principal (soles)
-        Light::new(Vec3::new(1.0, 7.0, -6.0), day_sun_material.emissive, 2.0),
-        Light::new(Vec3::new(6.0, 5.0, -7.5), day_sun_material.emissive, 1.5),
-        // Añade la luz de relleno
-        fill_light,
-    ];
-    // Crea el plano del suelo
-    // Crear el plano del suelo con el material de arena
-    let ground_plane = Plane::new(
-        Vec3::new(0.0, 0.0, 0.0), // Posición en Y = 0
-        Vec3::new(0.0, 1.0, 0.0), // Normal apuntando hacia arriba
-        sand_material.clone(),    // Material de arena para el suelo
-    );
-
-    let planes = vec![ground_plane];
-
-    // Crea cubos para la estructura
-    let cubes = vec![
-        // Arenisca para la cúpula
-        Cube::new(Point3::new(0.0, 0.5, 0.0), 1.0, sandstone_material.clone()), // Bloque central
-        Cube::new(Point3::new(1.0, 0.5, 0.0), 1.0, sandstone_material.clone()), // Bloque lateral (derecha)
-        Cube::new(Point3::new(-1.0, 0.5, 0.0), 1.0, sandstone_material.clone()), // Bloque lateral (izquierda)
-        Cube::new(Point3::new(0.0, 0.5, 1.0), 1.0, sandstone_material.clone()),  // Bloque trasero
-        Cube::new(Point3::new(0.0, 0.5, -1.0), 1.0, sandstone_material.clone()), // Bloque frontal
-        // Segunda capa de la cúpula, usando arcilla para detalles
-        Cube::new(Point3::new(0.5, 1.0, 0.0), 1.0, clay_material.clone()), // Bloque superior lateral (derecha)
-        Cube::new(Point3::new(-0.5, 1.0, 0.0), 1.0, clay_material.clone()), // Bloque superior lateral (izquierda)
-        Cube::new(Point3::new(0.0, 1.0, 0.5), 1.0, clay_material.clone()), // Bloque superior trasero
-        Cube::new(Point3::new(0.0, 1.0, -0.5), 1.0, clay_material.clone()), // Bloque superior frontal
-        // Detalles adicionales (cajas metálicas) y metal oxidado para desgaste
-        Cube::new(Point3::new(2.5, 0.25, 0.0), 0.5, metal_material.clone()), // Caja metálica (derecha)
-        Cube::new(
-            Point3::new(-2.5, 0.25, 0.0),
-            0.5,
-            rusted_metal_material.clone(),
-        ), // Caja de metal oxidado (izquierda)
-    ];
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    });
+    if let Err(e) = configure_threads(options.threads) {
+        eprintln!("No se pudo configurar el pool de hilos: {}", e);
+    }
+
+    // Inicialización del framebuffer y carga de la escena
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+    // Las texturas se conservan entre recargas de la escena
+    let mut textures = TextureRegistry::new();
+    let mut scene = Scene::load_with_textures(&options.scene, &mut textures).unwrap_or_else(|e| {
+        eprintln!("Error al cargar la escena: {}", e);
+        std::process::exit(1);
+    });
+    print_warnings(&scene);
+    if let Some(fov) = options.fov {
+        scene.camera.set_fov(fov.to_radians());
+    }
+    if let Some(projection) = options.projection {
+        scene.camera.projection = projection;
+    }
+    if let Some(aperture) = options.aperture {
+        scene.camera.aperture = aperture;
+    }
+    if let Some(focal_distance) = options.focal_distance {
+        scene.camera.focal_distance = focal_distance;
+    }
+    scene.camera.autofocus |= options.autofocus;
+
+    // Modo sin ventana: renderiza un único frame y lo guarda en disco
+    if let Some(output) = &options.output {
+        let start = Instant::now();
+        Renderer::new(headless_settings(&options)).render(&scene, &mut framebuffer);
+        println!(
+            "Renderizado {}x{} a {} spp en {:.2?}",
+            framebuffer.width,
+            framebuffer.height,
+            options.spp,
+            start.elapsed()
+        );
+
+        if let Err(e) = framebuffer.save(output) {
+            eprintln!("Error al guardar {}: {}", output, e);
+            std::process::exit(1);
+        }
+        println!("Imagen guardada en {}", output);
+        return;
+    }
+
+    // Animación sin ventana: un PNG numerado por frame a lo largo del recorrido de la cámara
+    if let Some(directory) = &options.animate {
+        if scene.camera_path.is_empty() {
+            eprintln!("La escena no define keyframes de cámara ([[keyframes]])");
+            std::process::exit(1);
+        }
+        if let Err(e) = std::fs::create_dir_all(directory) {
+            eprintln!("Error al crear {}: {}", directory, e);
+            std::process::exit(1);
+        }
+
+        let path = scene.camera_path.clone();
+        let duration = path.end_time() - path.start_time();
+        let frames = (duration * options.fps).floor() as usize + 1;
+        let renderer = Renderer::new(headless_settings(&options));
+        let start = Instant::now();
+        for frame in 0..frames {
+            path.apply(&mut scene.camera, path.start_time() + frame as f32 / options.fps);
+            renderer.render(&scene, &mut framebuffer);
+
+            let file = Path::new(directory).join(format!("frame_{:04}.png", frame + 1));
+            if let Err(e) = framebuffer.save(&file) {
+                eprintln!("Error al guardar {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+            println!("Frame {}/{} guardado en {}", frame + 1, frames, file.display());
+        }
+        println!("Animación de {} frames renderizada en {:.2?}", frames, start.elapsed());
+        return;
+    }
 
     // Crea la ventana
     let mut window = Window::new(
@@ -180,15 +128,41 @@ fn main() {
         panic!("Error al crear la ventana: {}", e);
     });
 
+    // Transformación de salida (exposición, curva de tono, sRGB) y antialiasing
+    let mut renderer = Renderer::new(RenderSettings {
+        output: options.output_transform,
+        ..RenderSettings::default()
+    });
+    let exposure_step = 0.25; // Pasos de exposición (EV) por pulsación
+
+    // Renderizado progresivo: acumula muestras mientras la escena no cambie
+    let mut progressive = true;
+    let mut last_view_state = None;
+    const MAX_PASSES: u32 = 256; // A partir de aquí la imagen se considera convergida
+
     // Velocidades para diversas acciones de la cámara
     let zoom_speed = 0.5; // Sensibilidad de zoom
     let orbit_speed = 0.01; // Sensibilidad de órbita
     let movement_speed = 0.8; // Sensibilidad de movimiento
+    let look_speed = 0.005; // Sensibilidad de la mirada en vuelo libre (radianes por píxel)
+    let fly_speed = 3.0; // Velocidad de vuelo libre (unidades por segundo)
+    let fov_step = 5f32.to_radians(); // Cambio del campo de visión por pulsación
+    let aperture_step = 0.02; // Cambio del radio de la apertura por pulsación
+    let focus_step = 1.1; // Factor de cambio de la distancia de enfoque por pulsación
+    let pan_speed = 0.002; // Sensibilidad de paneo (relativa a la distancia al objetivo)
 
     // Variables para rastrear la posición del mouse y el tiempo entre frames
-    let mut last_mouse_pos = None;
+    let mut camera_mode = CameraMode::Orbit;
+    let mut recorded_keyframes = 0; // Keyframes impresos con K, separados 2 segundos
+    let mut last_mouse_pos: Option<(f32, f32)> = None;
+    let mut last_pan_pos: Option<(f32, f32)> = None;
     let mut last_frame = Instant::now();
 
+    // Recarga en caliente: se revisan en disco la escena, sus texturas y modelos
+    let mut watcher = FileWatcher::new(&scene.sources);
+    let mut last_poll = Instant::now();
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
     // Bucle principal de renderizado
     while window.is_open() && !window.is_key_down(Key::Escape) {
         let now = Instant::now();
@@ -196,20 +170,59 @@ fn main() {
         last_frame = now;
         let delta_time_seconds = delta_time.as_secs_f32();
 
+        // Si algún archivo cambió, se recarga la escena conservando la cámara y la hora del día.
+        // Solo se vuelven a leer las texturas modificadas; si la escena tiene errores se mantiene la anterior.
+        if last_poll.elapsed() >= POLL_INTERVAL {
+            last_poll = Instant::now();
+            let changed = watcher.changed();
+            if !changed.is_empty() {
+                for path in &changed {
+                    textures.invalidate(path);
+                }
+                match Scene::load_with_textures(&options.scene, &mut textures) {
+                    Ok(mut reloaded) => {
+                        print_warnings(&reloaded);
+                        reloaded.camera = scene.camera.clone();
+                        reloaded.set_time_of_day(scene.skybox.is_day);
+                        watcher = FileWatcher::new(&reloaded.sources);
+                        scene = reloaded;
+                        renderer.reset();
+                        println!("Escena recargada ({} archivo(s) modificado(s))", changed.len());
+                    }
+                    Err(e) => eprintln!("Error al recargar la escena: {}", e),
+                }
+            }
+        }
+
         // Zoom usando la rueda del mouse
         if let Some(scroll) = window.get_scroll_wheel() {
-            camera.zoom(scroll.1 * zoom_speed * delta_time_seconds); // Ajusta el radio de la cámara
+            let amount = scroll.1 * zoom_speed * delta_time_seconds;
+            match camera_mode {
+                CameraMode::Orbit => scene.camera.zoom(amount), // Ajusta el radio de la cámara
+                // En vuelo libre avanza en la dirección de la vista, sin limitar la altura
+                CameraMode::FreeFly => scene.camera.move_local(amount, 0.0, 0.0),
+            }
         }
 
-        // Órbita de la cámara arrastrando con el botón izquierdo del mouse
+        // Alternar entre la cámara orbital y la de vuelo libre
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            camera_mode = camera_mode.next();
+            println!("Modo de cámara: {}", camera_mode.name());
+        }
+
+        // Órbita (o mirada en vuelo libre) arrastrando con el botón izquierdo del mouse
         if window.get_mouse_down(MouseButton::Left) {
             if let Some((x, y)) = window.get_mouse_pos(minifb::MouseMode::Clamp) {
                 if let Some((last_x, last_y)) = last_mouse_pos {
                     let delta_x = x - last_x;
                     let delta_y = y - last_y;
 
-                    // Órbita basada en el arrastre del mouse
-                    camera.orbit(delta_x as f32 * orbit_speed, delta_y as f32 * orbit_speed);
+                    match camera_mode {
+                        // Órbita basada en el arrastre del mouse
+                        CameraMode::Orbit => scene.camera.orbit(delta_x * orbit_speed, delta_y * orbit_speed),
+                        // Mirar alrededor (yaw/pitch) en primera persona
+                        CameraMode::FreeFly => scene.camera.look(delta_x * look_speed, -delta_y * look_speed),
+                    }
                 }
                 last_mouse_pos = Some((x, y));
             }
@@ -217,69 +230,200 @@ fn main() {
             last_mouse_pos = None;
         }
 
-        // Mover la cámara con las teclas WASD sin mover el objetivo
-        if window.is_key_down(Key::W) {
-            camera.move_up_global(movement_speed);
+        // Paneo de la cámara (y su objetivo) arrastrando con el botón derecho
+        if window.get_mouse_down(MouseButton::Right) {
+            if let Some((x, y)) = window.get_mouse_pos(minifb::MouseMode::Clamp) {
+                if let Some((last_x, last_y)) = last_pan_pos {
+                    scene.camera.pan(-(x - last_x) * pan_speed, (y - last_y) * pan_speed);
+                }
+                last_pan_pos = Some((x, y));
+            }
+        } else {
+            last_pan_pos = None;
+        }
+
+        // Tomar como nuevo objetivo el punto de la escena bajo el cursor
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            if let Some((x, y)) = window.get_mouse_pos(minifb::MouseMode::Discard) {
+                // Misma proporción que usa el renderizador
+                let mut camera = scene.camera.clone();
+                camera.aspect_ratio = framebuffer.width as f32 / framebuffer.height as f32;
+                let (origin, direction) = camera.primary_ray(x / framebuffer.width as f32, y / framebuffer.height as f32);
+                match scene.intersect(&origin, &direction) {
+                    Some(hit) => {
+                        scene.camera.set_target(Point3::from(hit.point));
+                        println!("Nuevo objetivo: ({:.2}, {:.2}, {:.2})", hit.point.x, hit.point.y, hit.point.z);
+                    }
+                    None => println!("No hay ningún objeto bajo el cursor"),
+                }
+            }
         }
-        if window.is_key_down(Key::S) {
-            camera.move_up_global(-movement_speed);
+
+        match camera_mode {
+            CameraMode::Orbit => {
+                // Mover la cámara con las teclas WASD sin mover el objetivo
+                if window.is_key_down(Key::W) {
+                    scene.camera.move_up_global(movement_speed);
+                }
+                if window.is_key_down(Key::S) {
+                    scene.camera.move_up_global(-movement_speed);
+                }
+
+                // Mover izquierda/derecha (teclas A/D) - a lo largo del eje X global
+                if window.is_key_down(Key::A) {
+                    scene.camera.move_right_global(-movement_speed);
+                }
+                if window.is_key_down(Key::D) {
+                    scene.camera.move_right_global(movement_speed);
+                }
+            }
+            CameraMode::FreeFly => {
+                // WASD relativo a la dirección de la vista, espacio/shift para subir/bajar
+                let step = fly_speed * delta_time_seconds;
+                let axis = |positive: Key, negative: Key| {
+                    window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+                };
+                let forward = axis(Key::W, Key::S);
+                let right = axis(Key::D, Key::A);
+                let up = axis(Key::Space, Key::LeftShift);
+                if forward != 0.0 || right != 0.0 || up != 0.0 {
+                    scene.camera.move_local(forward * step, right * step, up * step);
+                }
+            }
         }
 
-        // Mover izquierda/derecha (teclas A/D) - a lo largo del eje X global
-        if window.is_key_down(Key::A) {
-            camera.move_right_global(-movement_speed);
+        // Campo de visión vertical (teclas , y .)
+        if window.is_key_pressed(Key::Comma, KeyRepeat::Yes) {
+            scene.camera.set_fov(scene.camera.fov - fov_step);
+            println!("Campo de visión: {:.0}°", scene.camera.fov.to_degrees());
         }
-        if window.is_key_down(Key::D) {
-            camera.move_right_global(movement_speed);
+        if window.is_key_pressed(Key::Period, KeyRepeat::Yes) {
+            scene.camera.set_fov(scene.camera.fov + fov_step);
+            println!("Campo de visión: {:.0}°", scene.camera.fov.to_degrees());
         }
 
-        // Manejar la entrada para alternar entre día y noche
-        if window.is_key_pressed(Key::Key1, KeyRepeat::No) {
-            skybox.toggle_day_night();
+        // Imprime la pose actual como keyframe para pegarlo en el archivo de escena
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            let camera = &scene.camera;
+            println!(
+                "[[keyframes]]\ntime = {:.1}\nposition = [{:.3}, {:.3}, {:.3}]\ntarget = [{:.3}, {:.3}, {:.3}]\nfov = {:.1}\n",
+                recorded_keyframes as f32 * 2.0,
+                camera.position.x,
+                camera.position.y,
+                camera.position.z,
+                camera.target.x,
+                camera.target.y,
+                camera.target.z,
+                camera.fov.to_degrees()
+            );
+            recorded_keyframes += 1;
+        }
 
-            if skybox.is_day {
-                // Configuraciones para el día
+        // Proyección de la cámara (O)
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            scene.camera.projection = scene.camera.projection.next();
+            println!("Proyección: {}", scene.camera.projection.name());
+        }
 
-                // Actualizar materiales de los soles
-                suns[0].material = day_sun_material.clone();
-                suns[1].material = day_sun_material.clone();
+        // Profundidad de campo: apertura (9 y 0), distancia de enfoque (flechas) y autoenfoque (U)
+        if window.is_key_pressed(Key::Key0, KeyRepeat::Yes) {
+            scene.camera.aperture += aperture_step;
+            println!("Apertura: {:.2}", scene.camera.aperture);
+        }
+        if window.is_key_pressed(Key::Key9, KeyRepeat::Yes) {
+            scene.camera.aperture = (scene.camera.aperture - aperture_step).max(0.0);
+            println!("Apertura: {:.2}", scene.camera.aperture);
+        }
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            scene.camera.set_focal_distance(scene.camera.focal_distance * focus_step);
+            println!("Distancia de enfoque: {:.2}", scene.camera.focal_distance);
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            scene.camera.set_focal_distance(scene.camera.focal_distance / focus_step);
+            println!("Distancia de enfoque: {:.2}", scene.camera.focal_distance);
+        }
+        if window.is_key_pressed(Key::U, KeyRepeat::No) {
+            scene.camera.autofocus = !scene.camera.autofocus;
+            println!("Autoenfoque: {}", if scene.camera.autofocus { "activado" } else { "desactivado" });
+        }
 
-                // Actualizar propiedades de las luces
-                lights[0].color = day_sun_material.emissive;
-                lights[0].intensity = 2.0; // Intensidad para el día
+        // Manejar la entrada para alternar entre día y noche
+        if window.is_key_pressed(Key::Key1, KeyRepeat::No) {
+            scene.skybox.toggle_day_night();
+            // Materiales de los soles y luces definidos para cada modo en la escena
+            scene.set_time_of_day(scene.skybox.is_day);
+        }
 
-                lights[1].color = day_sun_material.emissive;
-                lights[1].intensity = 1.5; // Intensidad para el día
-            } else {
-                // Configuraciones para la noche (atardecer)
+        // Controles de la transformación de salida
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            renderer.settings.output.tone_map = renderer.settings.output.tone_map.next();
+            println!("Curva de tono: {}", renderer.settings.output.tone_map.name());
+        }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            renderer.settings.output.srgb = !renderer.settings.output.srgb;
+            println!("Codificación sRGB: {}", if renderer.settings.output.srgb { "activada" } else { "desactivada" });
+        }
+        if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+            renderer.settings.output.exposure += exposure_step;
+            println!("Exposición: {:+.2} EV", renderer.settings.output.exposure);
+        }
+        if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+            renderer.settings.output.exposure -= exposure_step;
+            println!("Exposición: {:+.2} EV", renderer.settings.output.exposure);
+        }
 
-                // Actualizar materiales de los soles
-                suns[0].material = night_sun1_material.clone();
-                suns[1].material = night_sun2_material.clone();
+        // Controles de antialiasing: muestras por píxel, patrón y filtro
+        if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+            renderer.settings.sampling.samples = (renderer.settings.sampling.samples * 4).min(64);
+            println!("Muestras por píxel: {}", renderer.settings.sampling.samples);
+        }
+        if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+            renderer.settings.sampling.samples = (renderer.settings.sampling.samples / 4).max(1);
+            println!("Muestras por píxel: {}", renderer.settings.sampling.samples);
+        }
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            renderer.settings.sampling.pattern = renderer.settings.sampling.pattern.next();
+            println!("Patrón de muestreo: {}", renderer.settings.sampling.pattern.name());
+        }
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            renderer.settings.sampling.filter = renderer.settings.sampling.filter.next();
+            println!("Filtro de reconstrucción: {}", renderer.settings.sampling.filter.name());
+        }
 
-                // Actualizar propiedades de las luces
-                lights[0].color = night_sun1_material.emissive;
-                lights[0].intensity = 1.0; // Intensidad reducida para la noche
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            progressive = !progressive;
+            renderer.reset();
+            println!("Renderizado progresivo: {}", if progressive { "activado" } else { "desactivado" });
+        }
 
-                lights[1].color = night_sun2_material.emissive;
-                lights[1].intensity = 0.8; // Intensidad reducida para la noche
-            }
+        // Cualquier cambio de cámara, cielo/luces o muestreo reinicia la acumulación
+        let view_state = (
+            scene.camera.view_matrix,
+            scene.camera.fov,
+            scene.camera.projection,
+            (scene.camera.aperture, scene.camera.focal_distance, scene.camera.autofocus),
+            scene.skybox.is_day,
+            renderer.settings.sampling,
+        );
+        if last_view_state != Some(view_state) {
+            renderer.reset();
+            last_view_state = Some(view_state);
         }
 
-        // Limpiar el framebuffer antes de renderizar
-        framebuffer.clear();
+        if progressive {
+            if renderer.passes() < MAX_PASSES {
+                renderer.render_progressive(&scene, &mut framebuffer);
+            } else {
+                // Imagen convergida: solo se vuelve a aplicar la transformación de salida
+                renderer.resolve(&mut framebuffer);
+            }
+        } else {
+            // Limpiar el framebuffer antes de renderizar
+            framebuffer.clear();
 
-        // Renderizar la escena
-        render(
-            &mut framebuffer,
-            &suns[..],   // Pass as slice
-            &cubes[..],  // Pass as slice
-            &planes[..], // Pass as slice
-            &camera,
-            &suns[..], // Pass as slice
-            &lights,
-            &skybox,
-        );
+            // Renderizar la escena
+            renderer.render(&scene, &mut framebuffer);
+        }
 
         // Actualizar la ventana con el nuevo frame
         window
@@ -1,7 +1,40 @@
 use crate::color::Color;
 use crate::texture::Texture;
+use nalgebra::Vector3 as Vec3;
 use std::sync::Arc;
 
+// How a material reflects light
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadingModel {
+    // Lambert + Blinn-Phong mixed by `albedo`, with `specular` as the exponent
+    Phong,
+    // Physically based: base color (`color`), `metallic`, `roughness` and
+    // `refractive_index`, shaded with a GGX microfacet BRDF
+    MetalRoughness,
+}
+
+// Direction of the green channel in tangent-space normal maps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMapConvention {
+    OpenGl,  // Green points towards +v (Blender, Unity)
+    DirectX, // Green points towards -v (Unreal, Substance default)
+}
+
+impl NormalMapConvention {
+    pub fn name(self) -> &'static str {
+        match self {
+            NormalMapConvention::OpenGl => "opengl",
+            NormalMapConvention::DirectX => "directx",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [NormalMapConvention::OpenGl, NormalMapConvention::DirectX]
+            .into_iter()
+            .find(|convention| convention.name() == name)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub color: Color,                   // Default color (used if no texture is applied)
@@ -11,7 +44,15 @@ pub struct Material {
     pub emissive: Color,                // Color for emissive materials
     pub has_texture: bool,              // If true, the material uses a texture
     pub texture: Option<Arc<Texture>>,  // Optional texture for materials
-    pub normal_map: Option<Arc<Texture>> // Optional normal map for materials
+    pub normal_map: Option<Arc<Texture>>, // Optional normal map for materials
+    pub normal_strength: f32,           // Scales the bumps of the normal map (0 = flat)
+    pub normal_convention: NormalMapConvention,
+    pub model: ShadingModel,
+    pub metallic: f32,                  // 0 = dielectric, 1 = metal (metal-roughness model)
+    pub roughness: f32,                 // 0 = mirror, 1 = fully rough (metal-roughness model)
+    // Grayscale maps (red channel) multiplied with `metallic` and `roughness`
+    pub metallic_map: Option<Arc<Texture>>,
+    pub roughness_map: Option<Arc<Texture>>,
 }
 
 impl Material {
@@ -25,6 +66,13 @@ impl Material {
             has_texture: false,
             texture: None,
             normal_map: None,
+            normal_strength: 1.0,
+            normal_convention: NormalMapConvention::OpenGl,
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_map: None,
+            roughness_map: None,
         }
     }
 
@@ -45,6 +93,13 @@ impl Material {
             has_texture: texture.is_some(),
             texture,
             normal_map,
+            normal_strength: 1.0,
+            normal_convention: NormalMapConvention::OpenGl,
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_map: None,
+            roughness_map: None,
         }
     }
 
@@ -63,19 +118,98 @@ impl Material {
             has_texture: false,
             texture: None,
             normal_map: None, // Set as None for materials without a normal map
+            normal_strength: 1.0,
+            normal_convention: NormalMapConvention::OpenGl,
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_map: None,
+            roughness_map: None,
+        }
+    }
+
+    // Physically based material with the given base color, metalness and
+    // roughness (both in [0, 1]) and index of refraction
+    pub fn metal_roughness(base_color: Color, metallic: f32, roughness: f32, refractive_index: f32) -> Self {
+        Material {
+            model: ShadingModel::MetalRoughness,
+            metallic,
+            roughness,
+            ..Material::new(base_color, [1.0, 0.0, 0.0, 0.0], 0.0, refractive_index)
         }
     }
 
     // Function to get the diffuse color based on texture coordinates (u, v)
     pub fn get_diffuse_color(&self, u: f32, v: f32) -> Color {
-        if self.has_texture {
-            if let Some(texture) = &self.texture {
-                let x = (u * (texture.width as f32 - 1.0)) as usize;
-                let y = ((1.0 - v) * (texture.height as f32 - 1.0)) as usize;
-                return texture.get_color(x, y);
+        self.diffuse_at(Some((u, v)), 0.0)
+    }
+
+    // Diffuse color at a hit. `footprint` is the size of the pixel in texture
+    // coordinates and selects the mip level (0 = full detail).
+    pub fn diffuse_at(&self, uv: Option<(f32, f32)>, footprint: f32) -> Color {
+        match (&self.texture, uv) {
+            (Some(texture), Some((u, v))) if self.has_texture => {
+                texture.sample_level(u, v, texture.level_for_footprint(footprint))
             }
+            _ => self.color,
+        }
+    }
+
+    // (metallic, roughness) at a hit, with the maps sampled like the diffuse texture
+    pub fn metal_roughness_at(&self, uv: Option<(f32, f32)>, footprint: f32) -> (f32, f32) {
+        let sample = |map: &Option<Arc<Texture>>, value: f32| match (map, uv) {
+            (Some(map), Some((u, v))) => value * map.sample_level(u, v, map.level_for_footprint(footprint)).r,
+            _ => value,
+        };
+        (
+            sample(&self.metallic_map, self.metallic).clamp(0.0, 1.0),
+            sample(&self.roughness_map, self.roughness).clamp(0.0, 1.0),
+        )
+    }
+
+    // Shading normal after applying the normal map. `tangents` are the surface
+    // derivatives (dP/du, dP/dv) at the hit; without them or without texture
+    // coordinates the normal is returned unchanged.
+    pub fn perturb_normal(
+        &self,
+        normal: Vec3<f32>,
+        tangents: Option<(Vec3<f32>, Vec3<f32>)>,
+        uv: Option<(f32, f32)>,
+        footprint: f32,
+    ) -> Vec3<f32> {
+        let (Some(normal_map), Some((tangent, bitangent)), Some((u, v))) = (&self.normal_map, tangents, uv) else {
+            return normal;
+        };
+
+        // Orthonormal frame: tangent made perpendicular to the normal (Gram-Schmidt),
+        // bitangent on the same side as dP/dv so mirrored UVs stay correct
+        let tangent = tangent - normal * normal.dot(&tangent);
+        if tangent.norm_squared() < 1e-12 {
+            return normal;
+        }
+        let tangent = tangent.normalize();
+        let mut frame_bitangent = normal.cross(&tangent);
+        if frame_bitangent.dot(&bitangent) < 0.0 {
+            frame_bitangent = -frame_bitangent;
+        }
+
+        // Texels store the tangent-space normal remapped from [-1, 1] to [0, 1]
+        let texel = normal_map.sample_level(u, v, normal_map.level_for_footprint(footprint));
+        let mut x = texel.r * 2.0 - 1.0;
+        let mut y = texel.g * 2.0 - 1.0;
+        let z = (texel.b * 2.0 - 1.0).max(0.0);
+        if self.normal_convention == NormalMapConvention::DirectX {
+            y = -y;
+        }
+        x *= self.normal_strength;
+        y *= self.normal_strength;
+
+        let perturbed = tangent * x + frame_bitangent * y + normal * z;
+        if perturbed.norm_squared() < 1e-12 {
+            normal
+        } else {
+            perturbed.normalize()
         }
-        self.color
     }
 
     pub fn yellow_sun() -> Self {
@@ -88,6 +222,13 @@ impl Material {
             has_texture: false,
             texture: None,
             normal_map: None, // Suns typically don't need normal maps
+            normal_strength: 1.0,
+            normal_convention: NormalMapConvention::OpenGl,
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_map: None,
+            roughness_map: None,
         }
     }
 
@@ -101,6 +242,49 @@ impl Material {
             has_texture: false,
             texture: None,
             normal_map: None,
+            normal_strength: 1.0,
+            normal_convention: NormalMapConvention::OpenGl,
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_map: None,
+            roughness_map: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_normal_map(sample: Color) -> Material {
+        let mut material = Material::new(Color::new(200, 200, 200), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        material.normal_map = Some(Arc::new(Texture::solid(sample)));
+        material
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_geometric_normal() {
+        let normal = Vec3::new(1.0, 2.0, -0.5).normalize();
+        // Tangent not yet perpendicular to the normal, as interpolated meshes give
+        let tangents = Some((Vec3::new(1.0, 0.0, 0.3), Vec3::new(0.0, 1.0, 0.0)));
+        for convention in [NormalMapConvention::OpenGl, NormalMapConvention::DirectX] {
+            let mut material = with_normal_map(Color::from_f32(0.5, 0.5, 1.0));
+            material.normal_convention = convention;
+            let perturbed = material.perturb_normal(normal, tangents, Some((0.3, 0.7)), 0.0);
+            assert!((perturbed - normal).norm() < 1e-5, "{:?} became {:?}", normal, perturbed);
+            assert!((perturbed.norm() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn tilted_normal_map_returns_unit_normals() {
+        let normal = Vec3::z();
+        let tangents = Some((Vec3::x(), Vec3::y()));
+        let material = with_normal_map(Color::from_f32(0.9, 0.5, 0.6));
+        let perturbed = material.perturb_normal(normal, tangents, Some((0.5, 0.5)), 0.0);
+        assert!((perturbed.norm() - 1.0).abs() < 1e-5);
+        // Red above 0.5 leans the normal towards the tangent
+        assert!(perturbed.x > 0.0 && perturbed.z > 0.0, "{:?}", perturbed);
+    }
+}
@@ -0,0 +1,323 @@
+// src/mesh.rs
+
+use crate::bvh::{Aabb, Bvh};
+use crate::color::Color;
+use crate::material::{Material, ShadingModel};
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::texture::TextureError;
+use crate::texture_registry::{TextureRegistry, TextureUsage};
+use crate::triangle::{barycentric, intersect_triangle, shading_normal, triangle_bounds, uv_scale, uv_tangents};
+use nalgebra::{Vector2 as Vec2, Vector3 as Vec3};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+// Indexed triangle mesh with a single material.
+// Faces are traversed through their own BVH, so the scene BVH only sees the
+// mesh bounds.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    positions: Vec<Vec3<f32>>,
+    normals: Option<Vec<Vec3<f32>>>, // Per-vertex normals, same length as `positions`
+    uvs: Option<Vec<Vec2<f32>>>,     // Per-vertex texture coordinates
+    faces: Vec<[u32; 3]>,
+    pub material: Material,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    // Panics if a face indexes past `positions` or the attribute lengths don't match
+    pub fn new(
+        positions: Vec<Vec3<f32>>,
+        normals: Option<Vec<Vec3<f32>>>,
+        uvs: Option<Vec<Vec2<f32>>>,
+        faces: Vec<[u32; 3]>,
+        material: Material,
+    ) -> Self {
+        assert!(normals.as_ref().is_none_or(|n| n.len() == positions.len()));
+        assert!(uvs.as_ref().is_none_or(|uv| uv.len() == positions.len()));
+        assert!(faces.iter().flatten().all(|&i| (i as usize) < positions.len()));
+
+        let mut mesh = Mesh {
+            positions,
+            normals,
+            uvs,
+            faces,
+            material,
+            bvh: Bvh::default(),
+        };
+        let bounds: Vec<Aabb> = (0..mesh.faces.len())
+            .map(|face| triangle_bounds(&mesh.vertices(face)))
+            .collect();
+        mesh.bvh = Bvh::from_bounds(&bounds);
+        mesh
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn vertices(&self, face: usize) -> [Vec3<f32>; 3] {
+        self.faces[face].map(|i| self.positions[i as usize])
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect> {
+        // Closest face as (face, t, u, v)
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        self.bvh.traverse(ray_origin, ray_direction, f32::INFINITY, |face| {
+            let hit = intersect_triangle(&self.vertices(face), ray_origin, ray_direction)
+                .filter(|&(t, _, _)| closest.is_none_or(|(_, closest_t, _, _)| t < closest_t));
+            if let Some((t, u, v)) = hit {
+                closest = Some((face, t, u, v));
+            }
+            ControlFlow::Continue(hit.map(|(t, _, _)| t))
+        });
+
+        let (face, t, u, v) = closest?;
+        let indices = self.faces[face].map(|i| i as usize);
+        let point = ray_origin + ray_direction * t;
+        let normals = self.normals.as_ref().map(|normals| indices.map(|i| normals[i]));
+        let normal = shading_normal(&self.vertices(face), normals, u, v);
+
+        let hit = Intersect::new(point, normal, t, self.material.clone());
+        Some(match &self.uvs {
+            Some(uvs) => {
+                let uvs = indices.map(|i| uvs[i]);
+                let uv = barycentric(uvs, u, v);
+                let vertices = self.vertices(face);
+                let hit = hit.with_uv((uv.x, uv.y), uv_scale(&vertices, &uvs));
+                match uv_tangents(&vertices, &uvs) {
+                    Some((tangent, bitangent)) => hit.with_tangents(tangent, bitangent),
+                    None => hit,
+                }
+            }
+            None => hit,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+}
+
+// Error while importing an OBJ file or its materials
+#[derive(Debug)]
+pub enum ObjError {
+    Load {
+        path: PathBuf,
+        source: tobj::LoadError,
+    },
+    Texture(TextureError),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Load { path, source } => {
+                write!(f, "{}: no se pudo cargar el modelo: {}", path.display(), source)
+            }
+            ObjError::Texture(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Load { source, .. } => Some(source),
+            ObjError::Texture(error) => Some(error),
+        }
+    }
+}
+
+// Loads every model of a Wavefront OBJ file as one mesh each, with materials
+// from its MTL library. Polygons are triangulated; texture paths in the MTL
+// file are relative to the OBJ file. Models without a material get a plain
+// grey one, and missing textures the registry's default placeholder.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Vec<Mesh>, ObjError> {
+    load_obj_with_textures(path, &mut TextureRegistry::new())
+}
+
+// Same as `load_obj`, sharing textures (and the missing-texture policy) with `textures`
+pub fn load_obj_with_textures<P: AsRef<Path>>(
+    path: P,
+    textures: &mut TextureRegistry,
+) -> Result<Vec<Mesh>, ObjError> {
+    let path = path.as_ref();
+    let load_error = |source| ObjError::Load {
+        path: path.to_path_buf(),
+        source,
+    };
+    let (models, materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS).map_err(load_error)?;
+    let materials = materials.map_err(load_error)?;
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let materials = materials
+        .iter()
+        .map(|material| convert_material(material, base_dir, textures))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let meshes = models
+        .into_iter()
+        .filter(|model| !model.mesh.indices.is_empty())
+        .map(|model| {
+            let mesh = model.mesh;
+            let vertex_count = mesh.positions.len() / 3;
+            let positions = mesh
+                .positions
+                .chunks_exact(3)
+                .map(|p| Vec3::new(p[0], p[1], p[2]))
+                .collect();
+            let normals = (mesh.normals.len() == vertex_count * 3).then(|| {
+                mesh.normals
+                    .chunks_exact(3)
+                    .map(|n| Vec3::new(n[0], n[1], n[2]))
+                    .collect()
+            });
+            let uvs = (mesh.texcoords.len() == vertex_count * 2).then(|| {
+                mesh.texcoords
+                    .chunks_exact(2)
+                    .map(|uv| Vec2::new(uv[0], uv[1]))
+                    .collect()
+            });
+            let faces = mesh
+                .indices
+                .chunks_exact(3)
+                .map(|f| [f[0], f[1], f[2]])
+                .collect();
+            let material = mesh
+                .material_id
+                .and_then(|id| materials.get(id).cloned())
+                .unwrap_or_else(default_material);
+            Mesh::new(positions, normals, uvs, faces, material)
+        })
+        .collect();
+    Ok(meshes)
+}
+
+fn default_material() -> Material {
+    Material::new(Color::new(180, 180, 180), [0.9, 0.1, 0.0, 0.0], 10.0, 1.0)
+}
+
+// Maps the MTL parameters onto the renderer's material model:
+// Kd -> color, Ks -> specular albedo, Ns -> exponent, d -> transparency,
+// Ni -> refractive index, Ke -> emission, map_Kd / norm -> textures.
+// The PBR extension (Pr / Pm roughness and metallic, map_Pr / map_Pm) switches
+// the material to the metal-roughness model; of the albedo only `d` is kept.
+fn convert_material(
+    material: &tobj::Material,
+    base_dir: &Path,
+    textures: &mut TextureRegistry,
+) -> Result<Material, ObjError> {
+    let to_color = |c: [f32; 3]| Color::from_f32(c[0], c[1], c[2]);
+    let specular = material.specular.map_or(0.0, |s| (s[0] + s[1] + s[2]) / 3.0);
+    let transparency = 1.0 - material.dissolve.unwrap_or(1.0);
+
+    let texture = material
+        .diffuse_texture
+        .as_deref()
+        .map(|file| textures.load(base_dir.join(file), TextureUsage::Color))
+        .transpose()
+        .map_err(ObjError::Texture)?;
+    let normal_map = material
+        .normal_texture
+        .as_deref()
+        .map(|file| textures.load(base_dir.join(file), TextureUsage::NormalMap))
+        .transpose()
+        .map_err(ObjError::Texture)?;
+
+    let mut converted = Material::new_with_texture(
+        material.diffuse.map_or(default_material().color, to_color),
+        [0.9, specular, 0.0, transparency],
+        material.shininess.unwrap_or(10.0),
+        material.optical_density.unwrap_or(1.0),
+        texture,
+        normal_map,
+    );
+    if let Some(emission) = material.unknown_param.get("Ke").and_then(|ke| parse_rgb(ke)) {
+        converted.emissive = to_color(emission);
+    }
+
+    let param = |key: &str| material.unknown_param.get(key).map(|value| value.trim());
+    let mut pbr_map = |key: &str| {
+        param(key)
+            .map(|file| textures.load(base_dir.join(file), TextureUsage::Scalar))
+            .transpose()
+            .map_err(ObjError::Texture)
+    };
+    let roughness_map = pbr_map("map_Pr")?;
+    let metallic_map = pbr_map("map_Pm")?;
+    let roughness = param("Pr").and_then(|value| value.parse::<f32>().ok());
+    let metallic = param("Pm").and_then(|value| value.parse::<f32>().ok());
+    if roughness.is_some() || metallic.is_some() || roughness_map.is_some() || metallic_map.is_some() {
+        converted.model = ShadingModel::MetalRoughness;
+        converted.albedo = [1.0, 0.0, 0.0, transparency];
+        converted.metallic = metallic.unwrap_or(0.0).clamp(0.0, 1.0);
+        converted.roughness = roughness.unwrap_or(0.5).clamp(0.0, 1.0);
+        converted.metallic_map = metallic_map;
+        converted.roughness_map = roughness_map;
+        if material.optical_density.is_none() {
+            converted.refractive_index = 1.5;
+        }
+    }
+    Ok(converted)
+}
+
+fn parse_rgb(value: &str) -> Option<[f32; 3]> {
+    let mut values = value.split_whitespace().map(|v| v.parse::<f32>().ok());
+    Some([values.next()??, values.next()??, values.next()??])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit quad in the XY plane facing +Z, as two triangles sharing an edge
+    const QUAD: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1
+f 1/1/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn loads_an_obj_with_normals_and_uvs() {
+        let path = std::env::temp_dir().join(format!("tatooine-{}-quad.obj", std::process::id()));
+        std::fs::write(&path, QUAD).unwrap();
+        let meshes = load_obj(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(meshes.len(), 1);
+        let mesh = &meshes[0];
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.positions.len(), 4);
+        assert!(mesh.normals.as_ref().unwrap().iter().all(|n| *n == Vec3::z()));
+        assert_eq!(mesh.uvs.as_ref().unwrap().len(), 4);
+
+        // Texture coordinates and normal interpolated at a hit on the second triangle
+        let hit = mesh
+            .ray_intersect(&Vec3::new(0.25, 0.75, 1.0), &Vec3::new(0.0, 0.0, -1.0))
+            .unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert!((hit.normal - Vec3::z()).norm() < 1e-6);
+        let (u, v) = hit.uv.unwrap();
+        assert!((u - 0.25).abs() < 1e-6 && (v - 0.75).abs() < 1e-6);
+    }
+}
@@ -1,4 +1,5 @@
 use nalgebra::Vector3 as Vec3;
+use crate::bvh::Aabb;
 use crate::material::Material;
 use crate::ray_intersect::{Intersect, RayIntersect};
 use crate::color::Color;
@@ -22,17 +23,13 @@ impl Plane {
 
     // Método para obtener el color en un punto, considerando la textura
     pub fn get_color_at(&self, point: &Vec3<f32>) -> Color {
-        if let Some(texture) = &self.material.texture {
-            // Mapeo UV básico
-            let u = (point.x - self.point.x).abs() % 1.0; // Ajusta según el tamaño de la textura
-            let v = (point.z - self.point.z).abs() % 1.0;
-            let x = (u * (texture.width as f32)) as usize;
-            let y = (v * (texture.height as f32)) as usize;
-            texture.get_color(x, y)
-        } else {
-            // Si no hay textura, usar el color base
-            self.material.color
-        }
+        let (u, v) = Plane::uv(point);
+        self.material.get_diffuse_color(u, v)
+    }
+
+    // Coordenadas de textura: la huella de 10x10 unidades cubre la textura una vez
+    fn uv(point: &Vec3<f32>) -> (f32, f32) {
+        ((point.x + 5.0) / 10.0, (point.z + 5.0) / 10.0)
     }
 }
 
@@ -50,29 +47,44 @@ impl RayIntersect for Plane {
                     return None;  // The intersection is outside the plane's bounds
                 }
 
-                // Calculate texture coordinates (u, v) for the normal map
-                // Assuming the plane is mapped from -5 to 5 in world space to 0 to 1 in texture space
-                let u = (hit_point.x + 5.0) / 10.0;
-                let v = (hit_point.z + 5.0) / 10.0;
+                // Texture coordinates, shared by the texture and the normal map
+                let (u, v) = Plane::uv(&hit_point);
 
-                // Perturb the normal using the normal map if it exists
-                let mut normal = self.normal;
-                if let Some(normal_map) = &self.material.normal_map {
-                    let normal_color = normal_map.get_color((u * (normal_map.width - 1) as f32) as usize, (v * (normal_map.height - 1) as f32) as usize);
-                    
-                    // Convert the normal color from RGB to a perturbation vector
-                    let perturbation = Vec3::new(
-                        normal_color.r as f32 / 255.0 * 2.0 - 1.0,
-                        normal_color.g as f32 / 255.0 * 2.0 - 1.0,
-                        normal_color.b as f32 / 255.0 * 2.0 - 1.0
-                    );
-                    normal = (normal + perturbation).normalize();  // Apply the normal perturbation
-                }
-
-                return Some(Intersect::new(hit_point, normal, t, self.material.clone()));
+                // u grows along X and v along Z (see `uv`); the normal map is applied by the renderer
+                return Some(
+                    Intersect::new(hit_point, self.normal, t, self.material.clone())
+                        .with_uv((u, v), 0.1)
+                        .with_tangents(Vec3::x(), Vec3::z()),
+                );
             }
         }
         None
     }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Vertical planes are unbounded in Y within the 10x10 footprint
+        if self.normal.y.abs() < 1e-6 {
+            return Aabb::infinite();
+        }
+
+        // Height of the plane at each corner of the footprint
+        let height_at = |x: f32, z: f32| {
+            self.point.y
+                - (self.normal.x * (x - self.point.x) + self.normal.z * (z - self.point.z)) / self.normal.y
+        };
+        let corners = [height_at(-5.0, -5.0), height_at(5.0, -5.0), height_at(-5.0, 5.0), height_at(5.0, 5.0)];
+        let min_y = corners.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max_y = corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+
+        Aabb::new(Vec3::new(-5.0, min_y - 1e-3, -5.0), Vec3::new(5.0, max_y + 1e-3, 5.0))
+    }
 }
 
@@ -1,5 +1,6 @@
 // ray_intersect.rs
 
+use crate::bvh::Aabb;
 use crate::material::Material;
 use nalgebra::Vector3 as Vec3;
 
@@ -10,6 +11,12 @@ pub struct Intersect {
     pub distance: f32,
     pub is_intersecting: bool,
     pub material: Material,
+    // Texture coordinates at the hit, and how fast they change per world unit
+    // (used to pick the mip level); None if the primitive has no mapping
+    pub uv: Option<(f32, f32)>,
+    pub uv_scale: f32,
+    // World-space directions in which u and v grow (dP/du, dP/dv), for normal mapping
+    pub tangents: Option<(Vec3<f32>, Vec3<f32>)>,
 }
 
 impl Intersect {
@@ -20,9 +27,23 @@ impl Intersect {
             distance,
             is_intersecting: true,
             material,
+            uv: None,
+            uv_scale: 0.0,
+            tangents: None,
         }
     }
 
+    pub fn with_uv(mut self, uv: (f32, f32), uv_scale: f32) -> Self {
+        self.uv = Some(uv);
+        self.uv_scale = uv_scale;
+        self
+    }
+
+    pub fn with_tangents(mut self, tangent: Vec3<f32>, bitangent: Vec3<f32>) -> Self {
+        self.tangents = Some((tangent, bitangent));
+        self
+    }
+
     pub fn empty() -> Self {
         Intersect {
             point: Vec3::zeros(),
@@ -30,10 +51,37 @@ impl Intersect {
             distance: 0.0,
             is_intersecting: false,
             material: Material::black(),
+            uv: None,
+            uv_scale: 0.0,
+            tangents: None,
         }
     }
 }
 // Define the RayIntersect trait
-pub trait RayIntersect {
+pub trait RayIntersect: Send + Sync {
     fn ray_intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect>;
+    // World-space bounds used by the BVH (Aabb::infinite() if unbounded)
+    fn bounding_box(&self) -> Aabb;
+    // Material of the object, e.g. to swap it when the time of day changes
+    fn material(&self) -> &Material;
+    fn material_mut(&mut self) -> &mut Material;
+}
+
+// Lets scenes store heterogeneous primitives as `Box<dyn RayIntersect>`
+impl<T: RayIntersect + ?Sized> RayIntersect for Box<T> {
+    fn ray_intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect> {
+        (**self).ray_intersect(ray_origin, ray_direction)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn material(&self) -> &Material {
+        (**self).material()
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        (**self).material_mut()
+    }
 }
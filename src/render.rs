@@ -1,243 +1,524 @@
 use nalgebra_glm::{Vec3};
-use crate::camera::Camera;
+use crate::brdf;
+use crate::camera::{Camera, Projection};
 use crate::color::Color;
-use crate::cube::Cube;
 use crate::framebuffer::Framebuffer;
-use crate::light::Light;
-use crate::material::Material;
-use crate::plane::Plane;
-use crate::ray_intersect::{Intersect, RayIntersect};
-use crate::sphere::Sphere;
-use crate::skybox::Skybox;
-
-fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32, eta_i: f32) -> Option<Vec3> {
-    let cosi = incident.dot(normal).clamp(-1.0, 1.0);
-    let eta = eta_i / eta_t;
-    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
+use crate::material::ShadingModel;
+use crate::ray_intersect::Intersect;
+use crate::sampling::{SamplePattern, SamplingSettings};
+use crate::scene::Scene;
+use crate::tonemap::OutputTransform;
+use rand::Rng;
+use rayon::prelude::*;
+
+// Secondary rays whose contribution to the pixel falls below this are not traced
+const MIN_THROUGHPUT: f32 = 1e-2;
+
+// n_incident / n_transmitted for a ray entering (`front_face`) or leaving a
+// dielectric of index `ior` surrounded by air
+fn relative_ior(ior: f32, front_face: bool) -> f32 {
+    if front_face {
+        1.0 / ior
+    } else {
+        ior
+    }
+}
+
+// Refracted direction of a unit `incident` ray through a surface whose `normal`
+// faces the ray, with `eta` = n_incident / n_transmitted; None on total internal reflection
+fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = -incident.dot(normal).clamp(-1.0, 1.0);
+    let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
 
     if k < 0.0 {
         None
     } else {
-        Some(eta * incident + (eta * cosi - k.sqrt()) * normal)
+        Some(eta * incident + (eta * cos_i - k.sqrt()) * normal)
     }
 }
 
-fn is_in_frustum(camera: &Camera, cube: &Cube) -> bool {
-    let min_bound = cube.center.coords - Vec3::new(cube.size / 2.0, cube.size / 2.0, cube.size / 2.0);
-    let max_bound = cube.center.coords + Vec3::new(cube.size / 2.0, cube.size / 2.0, cube.size / 2.0);
+// Configura el número de hilos del pool global de rayon (0 = todos los núcleos).
+// Falla si el pool global ya estaba creado.
+pub fn configure_threads(threads: usize) -> Result<(), rayon::ThreadPoolBuildError> {
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()
+}
+
+// Per-frame settings that don't belong to the scene itself
+#[derive(Debug, Clone, Default)]
+pub struct RenderSettings {
+    pub output: OutputTransform,     // Radiance -> 8-bit conversion
+    pub sampling: SamplingSettings, // Anti-aliasing samples per pixel
+}
+
+// Floating-point accumulation buffer for progressive rendering.
+// Holds the filter-weighted sum of every sample traced since the last reset.
+pub struct Accumulator {
+    pub width: usize,
+    pub height: usize,
+    color_sum: Vec<Color>,
+    weight_sum: Vec<f32>,
+    pub passes: u32,
+}
+
+impl Accumulator {
+    pub fn new(width: usize, height: usize) -> Self {
+        Accumulator {
+            width,
+            height,
+            color_sum: vec![Color::black(); width * height],
+            weight_sum: vec![0.0; width * height],
+            passes: 0,
+        }
+    }
 
-    let frustum_min = camera.position.coords - Vec3::new(10.0, 10.0, 10.0);
-    let frustum_max = camera.position.coords + Vec3::new(10.0, 10.0, 10.0);
+    // Discards every accumulated sample (camera, lights or sky changed)
+    pub fn reset(&mut self) {
+        self.color_sum.fill(Color::black());
+        self.weight_sum.fill(0.0);
+        self.passes = 0;
+    }
 
-    (min_bound.x >= frustum_min.x && max_bound.x <= frustum_max.x)
-        && (min_bound.y >= frustum_min.y && max_bound.y <= frustum_max.y)
-        && (min_bound.z >= frustum_min.z && max_bound.z <= frustum_max.z)
+    // Writes the current average to the framebuffer without tracing new samples
+    pub fn resolve(&self, framebuffer: &mut Framebuffer, output: &OutputTransform) {
+        framebuffer
+            .buffer
+            .par_iter_mut()
+            .zip(self.color_sum.par_iter())
+            .zip(self.weight_sum.par_iter())
+            .for_each(|((pixel, &color), &weight)| {
+                if weight > 0.0 {
+                    *pixel = output.encode(color * (1.0 / weight));
+                }
+            });
+    }
 }
 
-fn is_in_frustum_plane(_camera: &Camera, _plane: &Plane) -> bool {
-    true
+// Entry point for rendering a Scene. Keeps the accumulation buffer between
+// progressive passes; call `reset` whenever the camera, lights or sky change.
+pub struct Renderer {
+    pub settings: RenderSettings,
+    accumulator: Accumulator,
 }
 
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Renderer {
+            settings,
+            accumulator: Accumulator::new(0, 0),
+        }
+    }
+
+    // Renders a single frame from scratch
+    pub fn render(&self, scene: &Scene, framebuffer: &mut Framebuffer) {
+        render(framebuffer, scene, &self.settings);
+    }
 
+    // Adds one more pass to the accumulated image and displays the running average
+    pub fn render_progressive(&mut self, scene: &Scene, framebuffer: &mut Framebuffer) {
+        render_progressive(framebuffer, &mut self.accumulator, scene, &self.settings);
+    }
 
-fn render_lod_cube(framebuffer: &mut Framebuffer, cube: &Cube, camera: &Camera, lights: &[Light]) {
-    let distance = (camera.position.coords - cube.center.coords).magnitude();
+    // Re-encodes the accumulated image (e.g. after changing the output transform)
+    pub fn resolve(&self, framebuffer: &mut Framebuffer) {
+        self.accumulator.resolve(framebuffer, &self.settings.output);
+    }
 
-    if distance > 20.0 {
-        render_simple_cube(framebuffer, cube, camera, lights);
-    } else {
-        render_detailed_cube(framebuffer, cube, camera, lights);
+    // Number of progressive passes accumulated since the last reset
+    pub fn passes(&self) -> u32 {
+        self.accumulator.passes
     }
-}
 
-fn render_simple_cube(_framebuffer: &mut Framebuffer, _cube: &Cube, _camera: &Camera, _lights: &[Light]) {}
+    pub fn reset(&mut self) {
+        self.accumulator.reset();
+    }
+}
 
-fn render_detailed_cube(_framebuffer: &mut Framebuffer, _cube: &Cube, _camera: &Camera, _lights: &[Light]) {}
+// Renders a single frame from scratch
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, settings: &RenderSettings) {
+    let mut accumulator = Accumulator::new(framebuffer.width, framebuffer.height);
+    render_progressive(framebuffer, &mut accumulator, scene, settings);
+}
 
-pub fn render(
+// Adds one pass of samples to `accumulator` and writes the running average to the framebuffer.
+// Passes after the first jitter their samples so the image keeps converging.
+pub fn render_progressive(
     framebuffer: &mut Framebuffer,
-    objects: &[Sphere],
-    cubes: &[Cube],
-    planes: &[Plane],
-    camera: &Camera,
-    suns: &[Sphere],
-    lights: &[Light],
-    skybox: &Skybox,
+    accumulator: &mut Accumulator,
+    scene: &Scene,
+    settings: &RenderSettings,
 ) {
-    skybox.render_skybox(framebuffer);
+    if accumulator.width != framebuffer.width || accumulator.height != framebuffer.height {
+        *accumulator = Accumulator::new(framebuffer.width, framebuffer.height);
+    }
+
+    // Build the BVH (if needed) before fanning out to the workers
+    scene.bvh();
 
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = std::f32::consts::PI / 3.0;
-
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * fov.tan();
-            let screen_y = screen_y * fov.tan();
-
-            let ray_direction = Vec3::new(screen_x, screen_y, -1.0).normalize();
-            let rotated_direction = camera.transform_direction(&ray_direction);
-
-            let pixel_color = cast_ray(
-                &camera.position.coords,
-                &rotated_direction,
-                objects,
-                cubes,
-                planes,
-                suns,
-                lights,
-                0,
-                skybox
-            );
-
-            framebuffer.set_pixel(x as usize, y as usize, pixel_color.to_hex());
-        }
-    }
-}
+    // The image being rendered, not whatever the caller left in the camera, sets the aspect ratio
+    let mut camera = scene.focused_camera();
+    camera.aspect_ratio = width / height;
+    let camera = &camera;
+    let cone = RayCone::primary(camera, height);
+    let sampling = settings.sampling;
+    let pass = accumulator.passes;
+    // Regular patterns would land on the same positions every pass
+    let pattern = match sampling.pattern {
+        SamplePattern::Grid | SamplePattern::RotatedGrid if pass > 0 => SamplePattern::Stratified,
+        pattern => pattern,
+    };
 
-pub fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
-    objects: &[Sphere],
-    cubes: &[Cube],
-    planes: &[Plane],
-    suns: &[Sphere],
-    lights: &[Light],
-    depth: u32,
-    skybox: &Skybox,
-) -> Color {
-    if depth > 6 {
-        return Color::new(0, 0, 0);
-    }
+    // Each scanline is traced independently on the rayon pool
+    let row_width = framebuffer.width;
+    framebuffer
+        .buffer
+        .par_chunks_mut(row_width)
+        .zip(accumulator.color_sum.par_chunks_mut(row_width))
+        .zip(accumulator.weight_sum.par_chunks_mut(row_width))
+        .enumerate()
+        .for_each(|(y, ((row, color_row), weight_row))| {
+            let mut rng = rand::thread_rng();
+            for (x, pixel) in row.iter_mut().enumerate() {
+                for (dx, dy) in pattern.offsets(sampling.samples, pass, &mut rng) {
+                    // Sample position in pixel units, (0.5, 0.5) being the first pixel's center
+                    let sample_x = x as f32 + 0.5 + dx;
+                    let sample_y = y as f32 + 0.5 + dy;
+
+                    let lens = (rng.gen::<f32>(), rng.gen::<f32>());
+                    let (ray_origin, ray_direction) = camera.lens_ray(sample_x / width, sample_y / height, lens);
+                    let sample_color = trace(&ray_origin, &ray_direction, scene, 0, cone, 1.0);
+
+                    let weight = sampling.filter.weight(dx, dy);
+                    color_row[x] += sample_color * weight;
+                    weight_row[x] += weight;
+                }
 
-    let mut closest_intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+                let pixel_color = if weight_row[x] > 0.0 {
+                    color_row[x] * (1.0 / weight_row[x])
+                } else {
+                    Color::black()
+                };
 
-    for object in objects.iter().chain(suns.iter()) {
-        if let Some(intersect) = object.ray_intersect(ray_origin, ray_direction) {
-            if intersect.distance < zbuffer {
-                zbuffer = intersect.distance;
-                closest_intersect = intersect;
+                // Única conversión a 8 bits: al escribir en el framebuffer
+                *pixel = settings.output.encode(pixel_color);
             }
+        });
+
+    accumulator.passes += 1;
+}
+
+// Approximate footprint of a pixel along a ray: a cone with `width` at the ray
+// origin that grows by `spread` per unit of distance. Used to pick texture mip levels.
+#[derive(Debug, Clone, Copy, Default)]
+struct RayCone {
+    width: f32,
+    spread: f32,
+}
+
+impl RayCone {
+    // Cone of a primary ray for an image `height` pixels tall
+    fn primary(camera: &Camera, height: f32) -> Self {
+        let half_height = (camera.fov * 0.5).tan();
+        match camera.projection {
+            Projection::Perspective => RayCone {
+                width: 0.0,
+                spread: 2.0 * half_height / height,
+            },
+            Projection::Orthographic => RayCone {
+                width: 2.0 * half_height * camera.radius / height,
+                spread: 0.0,
+            },
+            Projection::Equirectangular => RayCone {
+                width: 0.0,
+                spread: std::f32::consts::PI / height,
+            },
+            Projection::Fisheye => RayCone {
+                width: 0.0,
+                spread: std::f32::consts::PI / (height * (camera.aspect_ratio * camera.aspect_ratio + 1.0).sqrt()),
+            },
         }
     }
 
-    for cube in cubes.iter() {
-        if let Some(intersect) = cube.ray_intersect(ray_origin, ray_direction) {
-            if intersect.distance < zbuffer {
-                zbuffer = intersect.distance;
-                closest_intersect = intersect;
-            }
-        }
+    fn width_at(&self, distance: f32) -> f32 {
+        self.width + self.spread * distance
     }
 
-    for plane in planes.iter() {
-        if let Some(intersect) = plane.ray_intersect(ray_origin, ray_direction) {
-            if intersect.distance < zbuffer {
-                zbuffer = intersect.distance;
-                closest_intersect = intersect;
-            }
+    // Cone continuing from a hit at `distance` (reflection/refraction; curvature is ignored)
+    fn bounce(&self, distance: f32) -> Self {
+        RayCone {
+            width: self.width_at(distance),
+            spread: self.spread,
         }
     }
+}
+
+// Color seen along a ray. Textures are sampled at full detail; the renderer
+// traces primary rays with their pixel footprint instead.
+pub fn cast_ray(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32) -> Color {
+    trace(ray_origin, ray_direction, scene, depth, RayCone::default(), 1.0)
+}
+
+// `throughput` is the largest weight the returned color will have in the pixel;
+// bounces that would end up below MIN_THROUGHPUT are skipped.
+fn trace(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, cone: RayCone, throughput: f32) -> Color {
+    if depth > 6 {
+        return Color::black();
+    }
+
+    let mut closest_intersect = match scene.intersect(ray_origin, ray_direction) {
+        Some(intersect) => intersect,
+        None => return scene.skybox.get_color(ray_direction),
+    };
+
+    // Primitives report outward normals; shading uses the side facing the ray,
+    // and a back-face hit means the ray is leaving the object
+    let front_face = ray_direction.dot(&closest_intersect.normal) < 0.0;
+    if !front_face {
+        closest_intersect.normal = -closest_intersect.normal;
+    }
 
-    if !closest_intersect.is_intersecting {
-        return skybox.get_color(ray_direction);
+    // Textured surfaces: sample at the mip level matching the pixel footprint
+    let footprint = cone.width_at(closest_intersect.distance) * closest_intersect.uv_scale;
+    closest_intersect.material.color = closest_intersect.material.diffuse_at(closest_intersect.uv, footprint);
+    closest_intersect.normal = closest_intersect.material.perturb_normal(
+        closest_intersect.normal,
+        closest_intersect.tangents,
+        closest_intersect.uv,
+        footprint,
+    );
+    let cone = cone.bounce(closest_intersect.distance);
+
+    if closest_intersect.material.model == ShadingModel::MetalRoughness {
+        return shade_metal_roughness(
+            &closest_intersect,
+            ray_direction,
+            front_face,
+            scene,
+            depth,
+            cone,
+            footprint,
+            throughput,
+        );
     }
 
-    if closest_intersect.material.emissive != Color::new(0, 0, 0) {
+    if !closest_intersect.material.emissive.is_black() {
         return closest_intersect.material.emissive;
     }
 
-    let mut color_accumulator = Color::new(0, 0, 0);
-    let ambient_light_intensity = 0.1;  // Ajusta este valor según lo que necesites
-    let ambient_color = closest_intersect.material.color * ambient_light_intensity;
-    for light in lights {
+    let mut color_accumulator = Color::black();
+    for light in &scene.lights {
         let light_dir = (light.position - closest_intersect.point).normalize();
         let shadow_origin = closest_intersect.point + light_dir * 1e-4;
 
-        let diffuse_intensity = closest_intersect.normal.dot(&light_dir).max(0.0) * light.intensity;
-        let diffuse = closest_intersect.material.albedo[0] * diffuse_intensity;
+        // Lights behind the surface contribute neither diffuse nor specular
+        let n_dot_l = closest_intersect.normal.dot(&light_dir);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let diffuse_intensity = n_dot_l * light.intensity;
+
+        let light_distance = (light.position - closest_intersect.point).magnitude();
+        let in_shadow = scene.bvh().occluded(scene.objects(), &shadow_origin, &light_dir, light_distance);
 
+        let light_intensity = if in_shadow { 0.1 } else { 1.0 };
+        let adjusted_diffuse = closest_intersect.material.albedo[0] * diffuse_intensity * light_intensity;
+        let diffuse = closest_intersect.material.color * light.color * adjusted_diffuse;
+
+        // Blinn-Phong: `specular` is the shininess exponent, albedo[1] the highlight weight.
+        // Highlights are dropped entirely in shadow, and an exponent of 0 means no highlight
+        // (x^0 = 1 would light the whole hemisphere).
+        let view_dir = -ray_direction;
+        let half_dir = (light_dir + view_dir).normalize();
+        let specular_intensity = if in_shadow || closest_intersect.material.specular <= 0.0 {
+            0.0
+        } else {
+            closest_intersect
+                .normal
+                .dot(&half_dir)
+                .max(0.0)
+                .powf(closest_intersect.material.specular)
+                * light.intensity
+        };
+        let specular = light.color * (closest_intersect.material.albedo[1] * specular_intensity);
+
+        color_accumulator += diffuse + specular;
+    }
 
-        let mut in_shadow = false;
+    // Transparent dielectrics split `transparency` between reflection and
+    // refraction with the Fresnel equations; total internal reflection reflects everything
+    let normal = closest_intersect.normal;
+    let reflectivity = closest_intersect.material.albedo[2];
+    let transparency = closest_intersect.material.albedo[3];
+    let ior = closest_intersect.material.refractive_index;
+    let eta = relative_ior(ior, front_face);
+    let (reflect_weight, refract_weight) = if transparency > 0.0 {
+        let fresnel = brdf::fresnel_dielectric(-ray_direction.dot(&normal), eta);
+        (reflectivity + transparency * fresnel, transparency * (1.0 - fresnel))
+    } else {
+        (reflectivity, 0.0)
+    };
 
-        for object in objects.iter().chain(suns.iter()) {
-            if let Some(shadow_intersect) = object.ray_intersect(&shadow_origin, &light_dir) {
-                if shadow_intersect.distance < (light.position - closest_intersect.point).magnitude() {
-                    in_shadow = true;
-                    break;
-                }
-            }
+    let mut color = color_accumulator * (1.0 - reflectivity - transparency);
+    if throughput * reflect_weight >= MIN_THROUGHPUT {
+        let reflect_dir = (ray_direction - 2.0 * ray_direction.dot(&normal) * normal).normalize();
+        let reflect_origin = closest_intersect.point + normal * 1e-4;
+        let reflected = trace(&reflect_origin, &reflect_dir, scene, depth + 1, cone, throughput * reflect_weight);
+        color += reflected * reflect_weight;
+    }
+    if throughput * refract_weight >= MIN_THROUGHPUT {
+        if let Some(refract_dir) = refract(ray_direction, &normal, eta) {
+            let refract_origin = closest_intersect.point - normal * 1e-4;
+            let refracted =
+                trace(&refract_origin, &refract_dir.normalize(), scene, depth + 1, cone, throughput * refract_weight);
+            color += refracted * refract_weight;
         }
+    }
+    color
+}
 
-        for cube in cubes.iter() {
-            if let Some(shadow_intersect) = cube.ray_intersect(&shadow_origin, &light_dir) {
-                if shadow_intersect.distance < (light.position - closest_intersect.point).magnitude() {
-                    in_shadow = true;
-                    break;
-                }
-            }
+// Metal-roughness shading: GGX lighting from every light, emission, and a
+// glossy reflection of the scene weighted by Fresnel. The light that isn't
+// reflected enters the surface: metals absorb it, dielectrics scatter it
+// diffusely or refract the `albedo[3]` share of it (tinted by the base color).
+// Rough reflections are jittered, so they converge over progressive passes.
+#[allow(clippy::too_many_arguments)]
+fn shade_metal_roughness(
+    hit: &Intersect,
+    ray_direction: &Vec3,
+    front_face: bool,
+    scene: &Scene,
+    depth: u32,
+    cone: RayCone,
+    footprint: f32,
+    throughput: f32,
+) -> Color {
+    let material = &hit.material;
+    let (metallic, roughness) = material.metal_roughness_at(hit.uv, footprint);
+    let base_color = material.color;
+    let ior = material.refractive_index;
+    let transparency = material.albedo[3];
+    let view_dir = -ray_direction;
+
+    let f0 = brdf::base_reflectance(base_color, metallic, ior);
+    let reflectance = brdf::fresnel_schlick_roughness(hit.normal.dot(&view_dir), f0, roughness);
+    let transmitted = brdf::transmitted(reflectance, metallic);
+    let diffuse = base_color * transmitted * ((1.0 - transparency) / std::f32::consts::PI);
+
+    let mut color = material.emissive;
+    for light in &scene.lights {
+        let to_light = light.position - hit.point;
+        let light_distance = to_light.magnitude();
+        let light_dir = to_light / light_distance;
+        let n_dot_l = hit.normal.dot(&light_dir);
+        if n_dot_l <= 0.0 {
+            continue;
         }
 
-        for plane in planes.iter() {
-            if let Some(shadow_intersect) = plane.ray_intersect(&shadow_origin, &light_dir) {
-                if shadow_intersect.distance < (light.position - closest_intersect.point).magnitude() {
-                    in_shadow = true;
-                    break;
-                }
+        let specular = brdf::specular(&hit.normal, &view_dir, &light_dir, f0, roughness);
+        let shadow_origin = hit.point + light_dir * 1e-4;
+        let in_shadow = scene.bvh().occluded(scene.objects(), &shadow_origin, &light_dir, light_distance);
+
+        // Scaled by pi so a white matte surface facing a light reflects `intensity`,
+        // like the Phong model; shadows keep 10% of the diffuse light and no highlight
+        let irradiance = light.color * (light.intensity * n_dot_l * std::f32::consts::PI);
+        color += if in_shadow {
+            diffuse * irradiance * 0.1
+        } else {
+            (diffuse + specular) * irradiance
+        };
+    }
+
+    // Refraction through the transparent share; on total internal reflection
+    // that light is reflected instead
+    let mut reflect_weight = reflectance;
+    let refract_weight = base_color * transmitted * transparency;
+    if throughput * refract_weight.max_channel() >= MIN_THROUGHPUT {
+        match refract(ray_direction, &hit.normal, relative_ior(ior, front_face)) {
+            Some(refract_dir) => {
+                let refract_origin = hit.point - hit.normal * 1e-4;
+                let refract_throughput = throughput * refract_weight.max_channel();
+                let refracted =
+                    trace(&refract_origin, &refract_dir.normalize(), scene, depth + 1, cone, refract_throughput);
+                color += refracted * refract_weight;
             }
+            None => reflect_weight += refract_weight,
         }
+    }
 
-        let light_intensity = if in_shadow { 0.1 } else { 1.0 };
-        let adjusted_diffuse = closest_intersect.material.albedo[0] * diffuse_intensity * light_intensity;
+    let reflect_throughput = throughput * reflect_weight.max_channel();
+    if reflect_throughput >= MIN_THROUGHPUT {
+        let reflect_dir = brdf::sample_reflection(&hit.normal, &view_dir, roughness, &mut rand::thread_rng());
+        let reflect_origin = hit.point + reflect_dir * 1e-4;
+        color += trace(&reflect_origin, &reflect_dir, scene, depth + 1, cone, reflect_throughput) * reflect_weight;
+    }
+    color
+}
 
-        color_accumulator = color_accumulator + closest_intersect.material.color * adjusted_diffuse;
-    }
-
-    let reflect_dir = ray_direction - 2.0 * ray_direction.dot(&closest_intersect.normal) * closest_intersect.normal;
-    let reflect_origin = closest_intersect.point + reflect_dir * 1e-4;
-    let reflect_color = cast_ray(
-        &reflect_origin,
-        &reflect_dir.normalize(),
-        objects,
-        cubes,
-        planes,
-        suns,
-        lights,
-        depth + 1,
-        skybox,
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::plane::Plane;
+    use nalgebra::{Point3, Vector3};
+
+    #[test]
+    fn exiting_rays_use_the_inverse_ratio() {
+        assert_eq!(relative_ior(1.5, true), 1.0 / 1.5);
+        assert_eq!(relative_ior(1.5, false), 1.5);
+    }
 
-    let refractive_index = closest_intersect.material.refractive_index;
-    let refract_color = if refractive_index > 1.0 {
-        if let Some(refract_dir) = refract(ray_direction, &closest_intersect.normal, refractive_index, 1.0) {
-            let refract_origin = closest_intersect.point + refract_dir * 1e-4;
-            cast_ray(
-                &refract_origin,
-                &refract_dir.normalize(),
-                objects,
-                cubes,
-                planes,
-                suns,
-                lights,
-                depth + 1,
-                skybox
-            )
-        }  else {
-            skybox.get_color(ray_direction)
-        }
-    } else {
-        skybox.get_color(ray_direction)
-    };
+    #[test]
+    fn refraction_follows_snell_and_reflects_past_the_critical_angle() {
+        // Surface normal flipped to face a ray travelling inside the glass
+        let normal = Vec3::new(0.0, 1.0, 0.0);
+        let eta = relative_ior(1.5, false);
+        let incident_at = |degrees: f32| {
+            let angle = degrees.to_radians();
+            Vec3::new(angle.sin(), -angle.cos(), 0.0)
+        };
+
+        // Below the critical angle (41.8 degrees): n1 sin(i) = n2 sin(t)
+        let refracted = refract(&incident_at(30.0), &normal, eta).unwrap();
+        assert!((refracted.norm() - 1.0).abs() < 1e-5);
+        assert!((refracted.x - 1.5 * 30f32.to_radians().sin()).abs() < 1e-5);
+        assert!(refracted.y < 0.0);
+
+        // Past it the ray is totally reflected
+        assert!(refract(&incident_at(60.0), &normal, eta).is_none());
+        let cos_i = -incident_at(60.0).dot(&normal);
+        assert_eq!(brdf::fresnel_dielectric(cos_i, eta), 1.0);
+    }
 
-    let reflectivity = closest_intersect.material.albedo[2];
-    let transparency = closest_intersect.material.albedo[3];
-    color_accumulator * (1.0 - reflectivity - transparency)
-        + reflect_color * reflectivity
-        + refract_color * transparency
-}
+    #[test]
+    fn lights_behind_a_surface_add_no_highlight() {
+        let camera = Camera::new(Point3::new(0.0, 0.0, 5.0), Point3::origin(), Vector3::y());
+        let mut scene = Scene::new(camera);
+        // Only the Blinn-Phong term, with a zero exponent
+        let shiny = Material::new(Color::new(255, 255, 255), [0.0, 1.0, 0.0, 0.0], 0.0, 1.0);
+        scene.add(Plane::new(Vector3::zeros(), Vector3::z(), shiny));
+        scene.add_light(Light::new(Vector3::new(3.0, 0.0, -1.0), Color::new(255, 255, 255), 1.0), None);
+
+        let (origin, direction) = scene.camera.primary_ray(0.5, 0.5);
+        let color = trace(&origin, &direction, &scene, 0, RayCone::default(), 1.0);
+        assert!(color.is_black(), "{:?}", color);
+    }
 
+    #[test]
+    fn transparent_metal_roughness_surfaces_let_light_through() {
+        let camera = Camera::new(Point3::new(0.0, 0.0, 5.0), Point3::origin(), Vector3::y());
+        let mut scene = Scene::new(camera);
+        let mut glass = Material::metal_roughness(Color::new(255, 255, 255), 0.0, 0.5, 1.5);
+        glass.albedo[3] = 1.0;
+        scene.add(Plane::new(Vector3::zeros(), Vector3::z(), glass));
+        let mut green = Material::black();
+        green.emissive = Color::from_f32(0.0, 1.0, 0.0);
+        scene.add(Plane::new(Vector3::new(0.0, 0.0, -2.0), Vector3::z(), green));
+
+        // Facing the pane, Fresnel reflects about 4% and the rest reaches the green wall
+        let (origin, direction) = scene.camera.primary_ray(0.5, 0.5);
+        let color = trace(&origin, &direction, &scene, 0, RayCone::default(), 1.0);
+        assert!(color.g > 0.9, "{:?}", color);
+    }
+}
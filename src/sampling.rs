@@ -0,0 +1,167 @@
+// src/sampling.rs
+
+use rand::Rng;
+
+// Distribution of sub-pixel sample positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplePattern {
+    Grid,        // Regular sqrt(N) x sqrt(N) grid
+    Stratified,  // Grid cells with a random jitter inside each cell
+    RotatedGrid, // Regular grid rotated by atan(1/2) (RGSS for N = 4)
+    Halton,      // Low-discrepancy Halton sequence in bases 2 and 3
+}
+
+impl SamplePattern {
+    pub fn next(self) -> Self {
+        match self {
+            SamplePattern::Grid => SamplePattern::Stratified,
+            SamplePattern::Stratified => SamplePattern::RotatedGrid,
+            SamplePattern::RotatedGrid => SamplePattern::Halton,
+            SamplePattern::Halton => SamplePattern::Grid,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SamplePattern::Grid => "grid",
+            SamplePattern::Stratified => "stratified",
+            SamplePattern::RotatedGrid => "rotated grid",
+            SamplePattern::Halton => "halton",
+        }
+    }
+
+    // Sample offsets relative to the pixel center, in [-0.5, 0.5)^2.
+    // Grid based patterns round `samples` to the nearest square.
+    // `pass` selects a different part of the Halton sequence on every call so
+    // successive passes don't repeat the same positions.
+    pub fn offsets<R: Rng>(self, samples: u32, pass: u32, rng: &mut R) -> Vec<(f32, f32)> {
+        let samples = samples.max(1);
+        let side = ((samples as f32).sqrt().round() as u32).max(1);
+        let cell = 1.0 / side as f32;
+
+        match self {
+            SamplePattern::Grid => grid(side)
+                .map(|(i, j)| ((i as f32 + 0.5) * cell - 0.5, (j as f32 + 0.5) * cell - 0.5))
+                .collect(),
+            SamplePattern::Stratified => grid(side)
+                .map(|(i, j)| {
+                    (
+                        (i as f32 + rng.gen::<f32>()) * cell - 0.5,
+                        (j as f32 + rng.gen::<f32>()) * cell - 0.5,
+                    )
+                })
+                .collect(),
+            SamplePattern::RotatedGrid => {
+                let angle = 0.5f32.atan();
+                let (sin, cos) = angle.sin_cos();
+                grid(side)
+                    .map(|(i, j)| {
+                        let x = (i as f32 + 0.5) * cell - 0.5;
+                        let y = (j as f32 + 0.5) * cell - 0.5;
+                        // Rotate around the pixel center and wrap back inside the pixel
+                        let rx = x * cos - y * sin;
+                        let ry = x * sin + y * cos;
+                        (wrap(rx), wrap(ry))
+                    })
+                    .collect()
+            }
+            SamplePattern::Halton => (0..samples)
+                .map(|i| {
+                    let index = pass.wrapping_mul(samples).wrapping_add(i) + 1;
+                    (radical_inverse(index, 2) - 0.5, radical_inverse(index, 3) - 0.5)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn grid(side: u32) -> impl Iterator<Item = (u32, u32)> {
+    (0..side).flat_map(move |j| (0..side).map(move |i| (i, j)))
+}
+
+// Wraps an offset back into [-0.5, 0.5)
+fn wrap(x: f32) -> f32 {
+    (x + 0.5).rem_euclid(1.0) - 0.5
+}
+
+fn radical_inverse(mut index: u32, base: u32) -> f32 {
+    let inv_base = 1.0 / base as f32;
+    let mut factor = inv_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f32 * factor;
+        index /= base;
+        factor *= inv_base;
+    }
+    result
+}
+
+// Maps a uniform sample in [0, 1)^2 to the unit disk (Shirley-Chiu concentric
+// mapping), keeping strata intact for lens sampling
+pub fn sample_disk(u: f32, v: f32) -> (f32, f32) {
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (radius, angle) = if a.abs() > b.abs() {
+        (a, std::f32::consts::FRAC_PI_4 * (b / a))
+    } else {
+        (b, std::f32::consts::FRAC_PI_2 - std::f32::consts::FRAC_PI_4 * (a / b))
+    };
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+// Weights the samples of a pixel by their distance to its center
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconstructionFilter {
+    Box,      // Every sample weighs the same
+    Tent,     // Linear falloff, 1 pixel radius
+    Gaussian, // Gaussian with sigma = 0.5 pixels
+}
+
+impl ReconstructionFilter {
+    pub fn next(self) -> Self {
+        match self {
+            ReconstructionFilter::Box => ReconstructionFilter::Tent,
+            ReconstructionFilter::Tent => ReconstructionFilter::Gaussian,
+            ReconstructionFilter::Gaussian => ReconstructionFilter::Box,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReconstructionFilter::Box => "box",
+            ReconstructionFilter::Tent => "tent",
+            ReconstructionFilter::Gaussian => "gaussian",
+        }
+    }
+
+    pub fn weight(self, dx: f32, dy: f32) -> f32 {
+        match self {
+            ReconstructionFilter::Box => 1.0,
+            ReconstructionFilter::Tent => (1.0 - dx.abs()).max(0.0) * (1.0 - dy.abs()).max(0.0),
+            ReconstructionFilter::Gaussian => {
+                const SIGMA: f32 = 0.5;
+                (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingSettings {
+    pub samples: u32, // Samples per pixel
+    pub pattern: SamplePattern,
+    pub filter: ReconstructionFilter,
+}
+
+impl Default for SamplingSettings {
+    // One ray through the pixel center, as without anti-aliasing
+    fn default() -> Self {
+        SamplingSettings {
+            samples: 1,
+            pattern: SamplePattern::Grid,
+            filter: ReconstructionFilter::Box,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// src/scene.rs
+
+use crate::animation::CameraPath;
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::scene_file::{load_scene, load_scene_with_textures, SceneError};
+use crate::skybox::Skybox;
+use nalgebra::Vector3 as Vec3;
+use crate::texture_registry::TextureRegistry;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// Escena completa: cámara, cielo, objetos y luces.
+// Los objetos son cualquier primitiva que implemente `RayIntersect` y se recorren
+// con un único BVH. Solo se modifican con `add`/`add_with_sunset`, que invalidan
+// el BVH; este se reconstruye la próxima vez que se renderiza.
+pub struct Scene {
+    pub camera: Camera,
+    pub skybox: Skybox,
+    pub lights: Vec<Light>,
+    // Recorrido de la cámara para animaciones (vacío si la escena no define keyframes)
+    pub camera_path: CameraPath,
+    // Archivos de los que se cargó la escena (el propio archivo, texturas y
+    // modelos), para recargarla cuando cambian
+    pub sources: Vec<PathBuf>,
+    // Avisos de la carga (texturas que faltan y se reemplazaron), para mostrarlos al usuario
+    pub warnings: Vec<String>,
+    objects: Vec<Box<dyn RayIntersect>>,
+    // (índice del objeto, material de día, material de atardecer)
+    sunset_materials: Vec<(usize, Material, Material)>,
+    // Variantes (día, atardecer) de cada luz, en el mismo orden que `lights`
+    light_settings: Vec<((Color, f32), (Color, f32))>,
+    bvh: OnceLock<Bvh>,
+}
+
+impl Scene {
+    // Escena vacía con cielo de día
+    pub fn new(camera: Camera) -> Self {
+        Scene {
+            camera,
+            skybox: Skybox::new(),
+            lights: Vec::new(),
+            warnings: Vec::new(),
+            camera_path: CameraPath::default(),
+            sources: Vec::new(),
+            objects: Vec::new(),
+            sunset_materials: Vec::new(),
+            light_settings: Vec::new(),
+            bvh: OnceLock::new(),
+        }
+    }
+
+    // Carga una escena desde un archivo TOML (ver `scene_file`)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+        load_scene(path)
+    }
+
+    // Carga una escena compartiendo las texturas de `textures`: solo se leen de
+    // disco las que no estén ya cargadas (o se hayan invalidado)
+    pub fn load_with_textures<P: AsRef<Path>>(path: P, textures: &mut TextureRegistry) -> Result<Scene, SceneError> {
+        load_scene_with_textures(path, textures)
+    }
+
+    pub fn add<T: RayIntersect + 'static>(&mut self, object: T) {
+        self.objects.push(Box::new(object));
+        self.bvh = OnceLock::new();
+    }
+
+    // Añade un objeto cuyo material se reemplaza por `sunset_material` en modo atardecer
+    pub fn add_with_sunset<T: RayIntersect + 'static>(&mut self, object: T, sunset_material: Material) {
+        let day = object.material().clone();
+        self.sunset_materials.push((self.objects.len(), day, sunset_material));
+        self.add(object);
+    }
+
+    // Añade una luz; `sunset` es su (color, intensidad) en modo atardecer
+    pub fn add_light(&mut self, light: Light, sunset: Option<(Color, f32)>) {
+        let day = (light.color, light.intensity);
+        self.light_settings.push((day, sunset.unwrap_or(day)));
+        self.lights.push(light);
+    }
+
+    pub fn objects(&self) -> &[Box<dyn RayIntersect>] {
+        &self.objects
+    }
+
+    // BVH de la escena, construido la primera vez que se necesita
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.objects))
+    }
+
+    // Intersección más cercana de un rayo con los objetos de la escena
+    pub fn intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect> {
+        self.bvh().intersect(&self.objects, ray_origin, ray_direction)
+    }
+
+    // Cámara con la que se renderiza: si tiene autoenfoque, enfocada en el
+    // objeto bajo el centro de la imagen (se mantiene el enfoque si no hay ninguno)
+    pub fn focused_camera(&self) -> Camera {
+        let mut camera = self.camera.clone();
+        if camera.autofocus {
+            let (origin, direction) = camera.primary_ray(0.5, 0.5);
+            if let Some(hit) = self.intersect(&origin, &direction) {
+                let forward = (camera.target - camera.position).normalize();
+                camera.focal_distance = hit.distance * direction.dot(&forward);
+            }
+        }
+        camera
+    }
+
+    // Aplica los materiales y luces de día o de atardecer
+    pub fn set_time_of_day(&mut self, is_day: bool) {
+        self.skybox.is_day = is_day;
+        for (index, day, sunset) in &self.sunset_materials {
+            *self.objects[*index].material_mut() = if is_day { day.clone() } else { sunset.clone() };
+        }
+        for (light, (day, sunset)) in self.lights.iter_mut().zip(&self.light_settings) {
+            let (color, intensity) = if is_day { *day } else { *sunset };
+            light.color = color;
+            light.intensity = intensity;
+        }
+    }
+}
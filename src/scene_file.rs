@@ -0,0 +1,940 @@
+// src/scene_file.rs
+//
+// Carga de escenas descritas en TOML. Ver `scenes/tatooine.toml` para un ejemplo completo.
+
+use crate::animation::{CameraPath, Keyframe};
+use crate::camera::{Camera, Projection, DEFAULT_FOV, MAX_FOV, MIN_FOV};
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::light::Light;
+use crate::material::{Material, NormalMapConvention, ShadingModel};
+use crate::mesh::{load_obj_with_textures, Mesh};
+use crate::plane::Plane;
+use crate::ray_intersect::RayIntersect;
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::texture::{TextureFilter, WrapMode};
+use crate::texture_registry::{FallbackTexture, TextureRegistry, TextureUsage};
+use crate::transform::{compose, invert, Transformed};
+use nalgebra::{Matrix4, Point3, Vector3 as Vec3};
+use serde::de::{self, value::MapAccessDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use toml::Spanned;
+
+// Error al cargar una escena, con la posición (línea y columna, desde 1) cuando se conoce
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => {
+                write!(f, "{}: no se pudo leer la escena: {}", path.display(), source)
+            }
+            SceneError::Invalid {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TimeOfDay {
+    Day,
+    Sunset,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDoc {
+    camera: CameraDoc,
+    #[serde(default)]
+    skybox: SkyboxDoc,
+    // Nombre -> ruta del archivo (relativa al archivo de escena) o tabla con opciones
+    #[serde(default)]
+    textures: BTreeMap<String, Spanned<TextureDoc>>,
+    // Reemplazo de las texturas que no se pueden cargar
+    #[serde(default)]
+    fallback_texture: FallbackTextureDoc,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDoc>,
+    #[serde(default)]
+    spheres: Vec<SphereDoc>,
+    #[serde(default)]
+    cubes: Vec<CubeDoc>,
+    #[serde(default)]
+    planes: Vec<PlaneDoc>,
+    #[serde(default)]
+    meshes: Vec<MeshDoc>,
+    #[serde(default)]
+    lights: Vec<LightDoc>,
+    // Recorrido de la cámara, en orden de tiempo
+    #[serde(default)]
+    keyframes: Vec<KeyframeDoc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDoc {
+    position: [f32; 3],
+    target: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    // Campo de visión vertical en grados
+    #[serde(default = "default_fov")]
+    fov: Spanned<f32>,
+    // "perspective", "orthographic", "equirectangular" o "fisheye"
+    #[serde(default)]
+    projection: Option<Spanned<String>>,
+    // Profundidad de campo: radio de la apertura (0 = estenopeica) y distancia
+    // de enfoque (por defecto la distancia al objetivo)
+    #[serde(default)]
+    aperture: Option<Spanned<f32>>,
+    #[serde(default)]
+    focal_distance: Option<Spanned<f32>>,
+    #[serde(default)]
+    autofocus: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkyboxDoc {
+    mode: TimeOfDay,
+}
+
+impl Default for SkyboxDoc {
+    fn default() -> Self {
+        SkyboxDoc {
+            mode: TimeOfDay::Day,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FallbackTextureDoc {
+    // Con `false` una textura que falta es un error
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default = "default_fallback_colors")]
+    colors: [[u8; 3]; 2],
+    #[serde(default = "default_fallback_cells")]
+    cells: Spanned<usize>,
+}
+
+impl Default for FallbackTextureDoc {
+    fn default() -> Self {
+        FallbackTextureDoc {
+            enabled: true,
+            colors: default_fallback_colors(),
+            cells: default_fallback_cells(),
+        }
+    }
+}
+
+// Ruta del archivo o tabla con opciones. Se deserializa a mano para que un campo
+// mal escrito en la tabla se informe por su nombre (con `untagged` solo se sabría
+// que no coincide con ninguna variante).
+enum TextureDoc {
+    File(String),
+    Options(TextureOptionsDoc),
+}
+
+impl<'de> Deserialize<'de> for TextureDoc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TextureVisitor;
+
+        impl<'de> Visitor<'de> for TextureVisitor {
+            type Value = TextureDoc;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("una ruta de archivo o una tabla { file, wrap, filter }")
+            }
+
+            fn visit_str<E: de::Error>(self, file: &str) -> Result<TextureDoc, E> {
+                Ok(TextureDoc::File(file.to_string()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<TextureDoc, A::Error> {
+                TextureOptionsDoc::deserialize(MapAccessDeserializer::new(map)).map(TextureDoc::Options)
+            }
+        }
+
+        deserializer.deserialize_any(TextureVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureOptionsDoc {
+    file: String,
+    #[serde(default)]
+    wrap: WrapDoc,
+    #[serde(default)]
+    filter: FilterDoc,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WrapDoc {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FilterDoc {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+impl TextureDoc {
+    fn file(&self) -> &str {
+        match self {
+            TextureDoc::File(file) | TextureDoc::Options(TextureOptionsDoc { file, .. }) => file,
+        }
+    }
+
+    fn wrap(&self) -> WrapMode {
+        match self {
+            TextureDoc::File(_) => WrapMode::Repeat,
+            TextureDoc::Options(options) => match options.wrap {
+                WrapDoc::Repeat => WrapMode::Repeat,
+                WrapDoc::Clamp => WrapMode::Clamp,
+                WrapDoc::Mirror => WrapMode::Mirror,
+            },
+        }
+    }
+
+    fn filter(&self) -> TextureFilter {
+        match self {
+            TextureDoc::File(_) => TextureFilter::Bilinear,
+            TextureDoc::Options(options) => match options.filter {
+                FilterDoc::Nearest => TextureFilter::Nearest,
+                FilterDoc::Bilinear => TextureFilter::Bilinear,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDoc {
+    color: [u8; 3],
+    // [difuso, especular, reflectividad, transparencia]; por defecto solo difuso
+    #[serde(default = "default_albedo")]
+    albedo: Spanned<[f32; 4]>,
+    #[serde(default = "default_specular")]
+    specular: Spanned<f32>,
+    // Por defecto 1.0, o 1.5 en los materiales PBR
+    #[serde(default)]
+    refractive_index: Option<Spanned<f32>>,
+    #[serde(default)]
+    emissive: Option<[u8; 3]>,
+    #[serde(default)]
+    texture: Option<Spanned<String>>,
+    #[serde(default)]
+    normal_map: Option<Spanned<String>>,
+    // Intensidad del relieve del normal map (0 = plano)
+    #[serde(default)]
+    normal_strength: Option<Spanned<f32>>,
+    // Convención del canal verde: "opengl" (por defecto) o "directx"
+    #[serde(default)]
+    normal_convention: Option<Spanned<String>>,
+    // Modelo metal-rugosidad (PBR): se usa si aparece alguno de estos campos.
+    // Los mapas son texturas en escala de grises que multiplican a los valores.
+    #[serde(default)]
+    metallic: Option<Spanned<f32>>,
+    #[serde(default)]
+    roughness: Option<Spanned<f32>>,
+    #[serde(default)]
+    metallic_map: Option<Spanned<String>>,
+    #[serde(default)]
+    roughness_map: Option<Spanned<String>>,
+}
+
+impl MaterialDoc {
+    fn is_metal_roughness(&self) -> bool {
+        self.metallic.is_some()
+            || self.roughness.is_some()
+            || self.metallic_map.is_some()
+            || self.roughness_map.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDoc {
+    center: [f32; 3],
+    radius: Spanned<f32>,
+    material: Spanned<String>,
+    #[serde(default)]
+    transform: Option<Spanned<TransformDoc>>,
+    // Material usado en modo atardecer (por defecto el mismo)
+    #[serde(default)]
+    sunset_material: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CubeDoc {
+    center: [f32; 3],
+    size: Spanned<f32>,
+    material: Spanned<String>,
+    #[serde(default)]
+    transform: Option<Spanned<TransformDoc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaneDoc {
+    point: [f32; 3],
+    #[serde(default = "default_up")]
+    normal: [f32; 3],
+    material: Spanned<String>,
+    #[serde(default)]
+    transform: Option<Spanned<TransformDoc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDoc {
+    file: Spanned<String>, // Modelo OBJ, relativo al archivo de escena
+    // Reemplaza los materiales del archivo MTL
+    #[serde(default)]
+    material: Option<Spanned<String>>,
+    #[serde(default)]
+    transform: Option<Spanned<TransformDoc>>,
+}
+
+// Traslación, rotación (ángulos de Euler XYZ en grados) y escala, aplicadas
+// en orden escala -> rotación -> traslación
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDoc {
+    #[serde(default)]
+    translation: [f32; 3],
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default = "default_scale")]
+    scale: [f32; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDoc {
+    time: Spanned<f32>, // Segundos
+    position: [f32; 3],
+    target: [f32; 3],
+    // Grados; por defecto el campo de visión de la cámara
+    #[serde(default)]
+    fov: Option<Spanned<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDoc {
+    position: [f32; 3],
+    color: [u8; 3],
+    intensity: Spanned<f32>,
+    // Color e intensidad en modo atardecer (por defecto los mismos)
+    #[serde(default)]
+    sunset_color: Option<[u8; 3]>,
+    #[serde(default)]
+    sunset_intensity: Option<Spanned<f32>>,
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_fov() -> Spanned<f32> {
+    Spanned::new(0..0, DEFAULT_FOV.to_degrees())
+}
+
+fn default_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_fallback_colors() -> [[u8; 3]; 2] {
+    [[255, 0, 255], [0, 0, 0]]
+}
+
+fn default_fallback_cells() -> Spanned<usize> {
+    Spanned::new(0..0, FallbackTexture::default().cells)
+}
+
+fn default_albedo() -> Spanned<[f32; 4]> {
+    Spanned::new(0..0, [1.0, 0.0, 0.0, 0.0])
+}
+
+fn default_specular() -> Spanned<f32> {
+    Spanned::new(0..0, 0.0)
+}
+
+fn vec3(v: [f32; 3]) -> Vec3<f32> {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+fn color(c: [u8; 3]) -> Color {
+    Color::new(c[0], c[1], c[2])
+}
+
+// (transformación, inversa) de un objeto
+type Placement = (Matrix4<f32>, Matrix4<f32>);
+
+// Convierte posiciones en bytes del archivo en errores con línea y columna
+struct Context<'a> {
+    path: &'a Path,
+    source: &'a str,
+}
+
+impl Context<'_> {
+    fn error(&self, span: Option<Range<usize>>, message: impl Into<String>) -> SceneError {
+        let (line, column) = match span {
+            Some(span) => {
+                let before = &self.source[..span.start.min(self.source.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                (line, column)
+            }
+            None => (1, 1),
+        };
+        SceneError::Invalid {
+            path: self.path.to_path_buf(),
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    // Matriz de la transformación y su inversa
+    fn transform(&self, doc: &Option<Spanned<TransformDoc>>) -> Result<Option<Placement>, SceneError> {
+        let Some(doc) = doc else {
+            return Ok(None);
+        };
+        let transform = doc.get_ref();
+        if transform.scale.contains(&0.0) {
+            return Err(self.error(Some(doc.span()), "la escala no puede ser 0"));
+        }
+        let matrix = compose(
+            vec3(transform.translation),
+            vec3(transform.rotation),
+            vec3(transform.scale),
+        );
+        // Escalas diminutas o valores no finitos dan una matriz que no se puede invertir
+        let inverse = invert(&matrix).ok_or_else(|| {
+            self.error(
+                Some(doc.span()),
+                "la transformación no es invertible (escala demasiado pequeña o valores no finitos)",
+            )
+        })?;
+        Ok(Some((matrix, inverse)))
+    }
+
+    // Valor opcional que debe estar en [0, 1]
+    fn unit_interval(&self, value: &Option<Spanned<f32>>, field: &str) -> Result<Option<f32>, SceneError> {
+        match value {
+            Some(value) if !(0.0..=1.0).contains(value.get_ref()) => Err(self.error(
+                Some(value.span()),
+                format!("{} debe estar entre 0 y 1", field),
+            )),
+            Some(value) => Ok(Some(*value.get_ref())),
+            None => Ok(None),
+        }
+    }
+
+    // Valor que debe ser finito y mayor que 0 (radios, tamaños)
+    fn positive(&self, value: &Spanned<f32>, field: &str) -> Result<f32, SceneError> {
+        let number = *value.get_ref();
+        if number.is_finite() && number > 0.0 {
+            Ok(number)
+        } else {
+            Err(self.error(
+                Some(value.span()),
+                format!("{} debe ser un número finito mayor que 0", field),
+            ))
+        }
+    }
+
+    // Valor que debe ser finito y no negativo (intensidades, exponentes)
+    fn non_negative(&self, value: &Spanned<f32>, field: &str) -> Result<f32, SceneError> {
+        let number = *value.get_ref();
+        if number.is_finite() && number >= 0.0 {
+            Ok(number)
+        } else {
+            Err(self.error(
+                Some(value.span()),
+                format!("{} debe ser un número finito no negativo", field),
+            ))
+        }
+    }
+
+    // `kind` lleva el artículo para concordar en género ("la textura", "el material")
+    fn lookup<'m, T>(
+        &self,
+        table: &'m HashMap<String, T>,
+        name: &Spanned<String>,
+        kind: &str,
+    ) -> Result<&'m T, SceneError> {
+        table.get(name.get_ref()).ok_or_else(|| {
+            self.error(
+                Some(name.span()),
+                format!("no existe {} '{}'", kind, name.get_ref()),
+            )
+        })
+    }
+}
+
+// Añade un objeto a la escena, con su transformación y material de atardecer si los tiene
+fn add_object<T: RayIntersect + Clone + 'static>(
+    scene: &mut Scene,
+    object: T,
+    transform: Option<Placement>,
+    sunset: Option<&Material>,
+) {
+    let place = |object, (transform, inverse)| Transformed::with_inverse(Arc::new(object), transform, inverse);
+    match (transform, sunset) {
+        (Some(transform), Some(sunset)) => scene.add_with_sunset(place(object, transform), sunset.clone()),
+        (Some(transform), None) => scene.add(place(object, transform)),
+        (None, Some(sunset)) => scene.add_with_sunset(object, sunset.clone()),
+        (None, None) => scene.add(object),
+    }
+}
+
+// Lee y valida un archivo de escena. Las rutas de las texturas son relativas al archivo.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    load_scene_with_textures(path, &mut TextureRegistry::new())
+}
+
+// Igual que `load_scene`, reutilizando las texturas ya cargadas en `registry`
+pub fn load_scene_with_textures<P: AsRef<Path>>(
+    path: P,
+    registry: &mut TextureRegistry,
+) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|source| SceneError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_scene(path, &source, registry)
+}
+
+fn parse_scene(path: &Path, source: &str, registry: &mut TextureRegistry) -> Result<Scene, SceneError> {
+    let context = Context { path, source };
+    let doc: SceneDoc =
+        toml::from_str(source).map_err(|e| context.error(e.span(), e.message()))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    // Descarta lo que haya quedado de una carga anterior que falló a medias
+    registry.take_requested();
+    registry.take_warnings();
+
+    let fallback = &doc.fallback_texture;
+    if *fallback.cells.get_ref() == 0 {
+        return Err(context.error(
+            Some(fallback.cells.span()),
+            "cells debe ser al menos 1",
+        ));
+    }
+    registry.set_fallback(fallback.enabled.then(|| FallbackTexture {
+        colors: fallback.colors.map(color),
+        cells: *fallback.cells.get_ref(),
+    }));
+
+    // Cada textura se carga al usarla: como color (decodificada de sRGB) en `texture`
+    // y como datos lineales en los normal maps y los mapas de rugosidad y metalicidad
+    let textures: HashMap<String, &Spanned<TextureDoc>> =
+        doc.textures.iter().map(|(name, texture)| (name.clone(), texture)).collect();
+    let mut load_texture = |name: &Spanned<String>, usage: TextureUsage| {
+        let texture_doc = context.lookup(&textures, name, "la textura")?;
+        registry
+            .load_with(
+                base_dir.join(texture_doc.get_ref().file()),
+                usage,
+                texture_doc.get_ref().wrap(),
+                texture_doc.get_ref().filter(),
+            )
+            .map_err(|e| context.error(Some(texture_doc.span()), e.to_string()))
+    };
+
+    let mut materials = HashMap::new();
+    for (name, material) in &doc.materials {
+        let texture = material
+            .texture
+            .as_ref()
+            .map(|name| load_texture(name, TextureUsage::Color))
+            .transpose()?;
+        let normal_map = material
+            .normal_map
+            .as_ref()
+            .map(|name| load_texture(name, TextureUsage::NormalMap))
+            .transpose()?;
+
+        let pbr = material.is_metal_roughness();
+        let albedo = *material.albedo.get_ref();
+        if !albedo.iter().all(|weight| (0.0..=1.0).contains(weight)) {
+            return Err(context.error(
+                Some(material.albedo.span()),
+                "los pesos de albedo deben estar entre 0 y 1",
+            ));
+        }
+        let specular = context.non_negative(&material.specular, "specular")?;
+        let refractive_index = match &material.refractive_index {
+            Some(value) => context.positive(value, "refractive_index")?,
+            None if pbr => 1.5,
+            None => 1.0,
+        };
+        let mut built = Material::new_with_texture(
+            color(material.color),
+            albedo,
+            specular,
+            refractive_index,
+            texture,
+            normal_map,
+        );
+        if let Some(emissive) = material.emissive {
+            built.emissive = color(emissive);
+        }
+        if let Some(strength) = &material.normal_strength {
+            if !strength.get_ref().is_finite() || *strength.get_ref() < 0.0 {
+                return Err(context.error(
+                    Some(strength.span()),
+                    "normal_strength debe ser un número no negativo",
+                ));
+            }
+            built.normal_strength = *strength.get_ref();
+        }
+        if let Some(convention) = &material.normal_convention {
+            built.normal_convention =
+                NormalMapConvention::from_name(convention.get_ref()).ok_or_else(|| {
+                    context.error(
+                        Some(convention.span()),
+                        format!(
+                            "convención de normal map desconocida: '{}' (usa \"opengl\" o \"directx\")",
+                            convention.get_ref()
+                        ),
+                    )
+                })?;
+        }
+        if pbr {
+            built.model = ShadingModel::MetalRoughness;
+            built.metallic = context.unit_interval(&material.metallic, "metallic")?.unwrap_or(0.0);
+            built.roughness = context.unit_interval(&material.roughness, "roughness")?.unwrap_or(0.5);
+            built.metallic_map = material
+                .metallic_map
+                .as_ref()
+                .map(|name| load_texture(name, TextureUsage::Scalar))
+                .transpose()?;
+            built.roughness_map = material
+                .roughness_map
+                .as_ref()
+                .map(|name| load_texture(name, TextureUsage::Scalar))
+                .transpose()?;
+        }
+        materials.insert(name.clone(), built);
+    }
+
+    let mut camera = Camera::new(
+        Point3::from(vec3(doc.camera.position)),
+        Point3::from(vec3(doc.camera.target)),
+        vec3(doc.camera.up),
+    );
+    let fov = *doc.camera.fov.get_ref();
+    if !(MIN_FOV.to_degrees()..=MAX_FOV.to_degrees()).contains(&fov) {
+        return Err(context.error(
+            Some(doc.camera.fov.span()),
+            format!(
+                "el campo de visión debe estar entre {:.0} y {:.0} grados",
+                MIN_FOV.to_degrees(),
+                MAX_FOV.to_degrees()
+            ),
+        ));
+    }
+    camera.set_fov(fov.to_radians());
+    if let Some(projection) = &doc.camera.projection {
+        camera.projection = Projection::from_name(projection.get_ref()).ok_or_else(|| {
+            context.error(
+                Some(projection.span()),
+                format!("proyección desconocida: '{}'", projection.get_ref()),
+            )
+        })?;
+    }
+    if let Some(aperture) = &doc.camera.aperture {
+        if !(aperture.get_ref().is_finite() && *aperture.get_ref() >= 0.0) {
+            return Err(context.error(
+                Some(aperture.span()),
+                "la apertura debe ser un número finito no negativo",
+            ));
+        }
+        camera.aperture = *aperture.get_ref();
+    }
+    if let Some(focal_distance) = &doc.camera.focal_distance {
+        if !(focal_distance.get_ref().is_finite() && *focal_distance.get_ref() > 0.0) {
+            return Err(context.error(
+                Some(focal_distance.span()),
+                "la distancia de enfoque debe ser un número finito mayor que 0",
+            ));
+        }
+        camera.focal_distance = *focal_distance.get_ref();
+    }
+    camera.autofocus = doc.camera.autofocus;
+
+    let mut keyframes: Vec<Keyframe> = Vec::new();
+    for keyframe in &doc.keyframes {
+        let fov = match &keyframe.fov {
+            Some(fov) => {
+                if !(MIN_FOV.to_degrees()..=MAX_FOV.to_degrees()).contains(fov.get_ref()) {
+                    return Err(context.error(Some(fov.span()), "campo de visión fuera de rango"));
+                }
+                fov.get_ref().to_radians()
+            }
+            None => camera.fov,
+        };
+        keyframes.push(Keyframe {
+            time: *keyframe.time.get_ref(),
+            position: Point3::from(vec3(keyframe.position)),
+            target: Point3::from(vec3(keyframe.target)),
+            fov,
+        });
+    }
+    let mut scene = Scene::new(camera);
+    // Los errores del recorrido se señalan en el tiempo del keyframe culpable
+    scene.camera_path = CameraPath::new(keyframes)
+        .map_err(|e| context.error(Some(doc.keyframes[e.index()].time.span()), e.to_string()))?;
+
+    for sphere in &doc.spheres {
+        let material = context.lookup(&materials, &sphere.material, "el material")?;
+        let sunset = sphere
+            .sunset_material
+            .as_ref()
+            .map(|name| context.lookup(&materials, name, "el material"))
+            .transpose()?;
+        let radius = context.positive(&sphere.radius, "radius")?;
+        let object = Sphere::new(vec3(sphere.center), radius, material.clone());
+        add_object(&mut scene, object, context.transform(&sphere.transform)?, sunset);
+    }
+
+    for cube in &doc.cubes {
+        let material = context.lookup(&materials, &cube.material, "el material")?;
+        let size = context.positive(&cube.size, "size")?;
+        let object = Cube::new(Point3::from(vec3(cube.center)), size, material.clone());
+        add_object(&mut scene, object, context.transform(&cube.transform)?, None);
+    }
+
+    for plane in &doc.planes {
+        let material = context.lookup(&materials, &plane.material, "el material")?;
+        let object = Plane::new(vec3(plane.point), vec3(plane.normal), material.clone());
+        add_object(&mut scene, object, context.transform(&plane.transform)?, None);
+    }
+
+    // Los modelos se cargan una sola vez por archivo y material, y cada entrada
+    // que los repite es una instancia que comparte la geometría
+    let mut models: HashMap<(String, Option<String>), Vec<Arc<Mesh>>> = HashMap::new();
+    for mesh in &doc.meshes {
+        let material = mesh
+            .material
+            .as_ref()
+            .map(|name| context.lookup(&materials, name, "el material"))
+            .transpose()?;
+        let key = (mesh.file.get_ref().clone(), mesh.material.as_ref().map(|m| m.get_ref().clone()));
+        if !models.contains_key(&key) {
+            let loaded = load_obj_with_textures(base_dir.join(mesh.file.get_ref()), registry)
+                .map_err(|e| context.error(Some(mesh.file.span()), e.to_string()))?;
+            let loaded = loaded
+                .into_iter()
+                .map(|mut model| {
+                    if let Some(material) = material {
+                        model.material = material.clone();
+                    }
+                    Arc::new(model)
+                })
+                .collect();
+            models.insert(key.clone(), loaded);
+        }
+
+        let (transform, inverse) = context
+            .transform(&mesh.transform)?
+            .unwrap_or_else(|| (Matrix4::identity(), Matrix4::identity()));
+        for model in &models[&key] {
+            scene.add(Transformed::with_inverse(model.clone(), transform, inverse));
+        }
+    }
+
+    for light in &doc.lights {
+        let intensity = context.non_negative(&light.intensity, "intensity")?;
+        let sunset_intensity = match &light.sunset_intensity {
+            Some(value) => Some(context.non_negative(value, "sunset_intensity")?),
+            None => None,
+        };
+        let sunset = (light.sunset_color.is_some() || sunset_intensity.is_some()).then(|| {
+            (
+                light.sunset_color.map_or(color(light.color), color),
+                sunset_intensity.unwrap_or(intensity),
+            )
+        });
+        scene.add_light(Light::new(vec3(light.position), color(light.color), intensity), sunset);
+    }
+
+    scene.set_time_of_day(doc.skybox.mode == TimeOfDay::Day);
+    // Solo los archivos que usa esta versión de la escena
+    let mut sources = registry.take_requested();
+    sources.insert(path.to_path_buf());
+    sources.extend(doc.meshes.iter().map(|mesh| base_dir.join(mesh.file.get_ref())));
+    scene.sources = sources.into_iter().collect();
+    scene.warnings = registry.take_warnings();
+    Ok(scene)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cámara y un material válidos (6 líneas) para las escenas de prueba
+    const HEADER: &str = "[camera]
+position = [0, 1, 5]
+target = [0, 0, 0]
+
+[materials.red]
+color = [255, 0, 0]
+";
+
+    // (línea, columna, mensaje) del error al cargar `body` tras la cabecera
+    fn error_at(body: &str) -> (usize, usize, String) {
+        let source = format!("{}{}", HEADER, body);
+        match parse_scene(Path::new("prueba.toml"), &source, &mut TextureRegistry::new()) {
+            Err(SceneError::Invalid { line, column, message, .. }) => (line, column, message),
+            Err(error) => panic!("error inesperado: {}", error),
+            Ok(_) => panic!("la escena debería ser inválida"),
+        }
+    }
+
+    #[test]
+    fn valid_scene_loads() {
+        let source = format!("{}\n[[spheres]]\ncenter = [0, 0, 0]\nradius = 1.0\nmaterial = \"red\"\n", HEADER);
+        assert!(parse_scene(Path::new("prueba.toml"), &source, &mut TextureRegistry::new()).is_ok());
+    }
+
+    #[test]
+    fn non_positive_sizes_report_their_value() {
+        let (line, column, message) = error_at("\n[[spheres]]\ncenter = [0, 0, 0]\nradius = 0.0\nmaterial = \"red\"\n");
+        assert_eq!((line, column), (10, 10));
+        assert!(message.contains("radius"), "{}", message);
+
+        let (line, column, message) = error_at("\n[[cubes]]\ncenter = [0, 0, 0]\nsize = nan\nmaterial = \"red\"\n");
+        assert_eq!((line, column), (10, 8));
+        assert!(message.contains("size"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_names_report_their_reference() {
+        let (line, column, message) = error_at("\n[[planes]]\npoint = [0, 0, 0]\nmaterial = \"blue\"\n");
+        assert_eq!((line, column), (10, 12));
+        assert_eq!(message, "no existe el material 'blue'");
+
+        let (line, column, message) = error_at("texture = \"sand\"\n");
+        assert_eq!((line, column), (7, 11));
+        assert_eq!(message, "no existe la textura 'sand'");
+    }
+
+    #[test]
+    fn invalid_values_report_their_position() {
+        // Campo desconocido, detectado por el propio parser de TOML
+        let (line, column, _) = error_at("shininess = 3\n");
+        assert_eq!((line, column), (7, 1));
+
+        // Transformación no invertible, señalada en la tabla `transform`
+        let (line, column, message) =
+            error_at("\n[[spheres]]\ncenter = [0, 0, 0]\nradius = 1.0\nmaterial = \"red\"\ntransform = { scale = [inf, 1, 1] }\n");
+        assert_eq!((line, column), (12, 13));
+        assert!(message.contains("invertible"), "{}", message);
+
+        // Tiempo de keyframe no finito
+        let (line, column, _) = error_at("\n[[keyframes]]\ntime = nan\nposition = [0, 1, 5]\ntarget = [0, 0, 0]\n");
+        assert_eq!((line, column), (9, 8));
+
+        // Keyframes fuera de orden: se señala el segundo
+        let key = |time: &str| format!("\n[[keyframes]]\ntime = {}\nposition = [0, 1, 5]\ntarget = [0, 0, 0]\n", time);
+        let (line, column, _) = error_at(&format!("{}{}", key("1.0"), key("0.5")));
+        assert_eq!((line, column), (14, 8));
+    }
+
+    #[test]
+    fn invalid_material_and_light_values_are_rejected() {
+        let (line, column, message) = error_at("refractive_index = 0.0\n");
+        assert_eq!((line, column), (7, 20));
+        assert!(message.contains("refractive_index"), "{}", message);
+
+        let (line, column, message) = error_at("albedo = [1.5, 0, 0, 0]\n");
+        assert_eq!((line, column), (7, 10));
+        assert!(message.contains("albedo"), "{}", message);
+
+        let (line, column, message) = error_at("specular = -1.0\n");
+        assert_eq!((line, column), (7, 12));
+        assert!(message.contains("specular"), "{}", message);
+
+        let light = |intensity: &str| {
+            format!("\n[[lights]]\nposition = [0, 5, 0]\ncolor = [255, 255, 255]\nintensity = {}\n", intensity)
+        };
+        for intensity in ["-1.0", "nan"] {
+            let (line, column, message) = error_at(&light(intensity));
+            assert_eq!((line, column), (11, 13));
+            assert!(message.contains("intensity"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn sources_only_list_files_in_use() {
+        let mut registry = TextureRegistry::new();
+        let path = Path::new("prueba.toml");
+        let textured = format!("{}texture = \"sand\"\n\n[textures]\nsand = \"sand.png\"\n", HEADER);
+        let scene = parse_scene(path, &textured, &mut registry).unwrap();
+        assert!(scene.sources.iter().any(|source| source.ends_with("sand.png")), "{:?}", scene.sources);
+
+        // Same registry (as when reloading), texture no longer referenced
+        let scene = parse_scene(path, HEADER, &mut registry).unwrap();
+        assert_eq!(scene.sources, vec![path.to_path_buf()]);
+    }
+
+    #[test]
+    fn misspelled_texture_keys_are_named() {
+        let (line, _, message) = error_at("\n[textures]\nsand = { file = \"sand.png\", wrapp = \"clamp\" }\n");
+        assert_eq!(line, 9);
+        assert!(message.contains("wrapp"), "{}", message);
+    }
+}
@@ -1,6 +1,8 @@
 use nalgebra_glm::{Vec3, dot};
+use crate::bvh::Aabb;
 use crate::material::Material;
 use crate::ray_intersect::{Intersect, RayIntersect};
+use std::f32::consts::PI;
 
 #[derive(Debug, Clone)]
 pub struct Sphere {
@@ -42,9 +44,36 @@ impl RayIntersect for Sphere {
             let normal = (point - self.center).normalize();
             let distance = t;
 
-            Some(Intersect::new(point, normal, distance, self.material.clone()))
+            // Latitude-longitude mapping; u wraps around the Y axis
+            let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+            let v = 0.5 + normal.y.clamp(-1.0, 1.0).asin() / PI;
+            let uv_scale = 1.0 / (PI * self.radius);
+
+            // u grows eastwards around the Y axis, v towards the north pole
+            let tangent = Vec3::new(-normal.z, 0.0, normal.x);
+            let tangent = if tangent.norm() > 1e-6 { tangent.normalize() } else { Vec3::x() };
+            let bitangent = tangent.cross(&normal);
+
+            Some(
+                Intersect::new(point, normal, distance, self.material.clone())
+                    .with_uv((u, v), uv_scale)
+                    .with_tangents(tangent, bitangent),
+            )
         } else {
             None
         }
     }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
 }
@@ -1,39 +1,252 @@
 extern crate image;
-use image::{ImageReader, Pixel, DynamicImage, GenericImageView};
+use image::{ImageReader, Pixel, DynamicImage, GenericImageView, ImageError, Rgb, RgbImage};
 use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use crate::color::Color;
-use nalgebra_glm::{Vec3};
 
+// Cómo se tratan las coordenadas de textura fuera de [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WrapMode {
+    Repeat, // La textura se repite
+    Clamp,  // Se extiende el borde
+    Mirror, // Se repite reflejada, sin costuras
+}
+
+// Interpolación entre texels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureFilter {
+    Nearest,  // Texel más cercano (para pixel art)
+    Bilinear, // Interpolación entre los 4 texels más cercanos
+}
+
+// Cómo se interpretan los valores de 8 bits de la imagen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Srgb,   // Colores (texturas difusas): se decodifican a lineal al cargar
+    Linear, // Datos (normal maps, rugosidad, metalicidad): se usan tal cual
+}
+
+// Un nivel de la cadena de mipmaps
+#[derive(Clone, PartialEq)]
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+}
+
+// La imagen, los texels y los mipmaps se comparten con `Arc`: clonar una textura
+// para cambiar su modo de repetición o su filtro no copia los datos
 #[derive(Clone, PartialEq)] // Added PartialEq for texture comparison
 pub struct Texture {
-    pub image: DynamicImage,
+    pub image: Arc<DynamicImage>,
     pub width: usize,
     pub height: usize,
-    pub color_array: Vec<Color>,
+    pub color_array: Arc<Vec<Color>>,
+    pub color_space: ColorSpace,
+    pub wrap: WrapMode,
+    pub filter: TextureFilter,
+    // Niveles 1.. de la cadena de mipmaps (el nivel 0 es `color_array`)
+    mip_levels: Arc<Vec<MipLevel>>,
 }
 
 impl Texture {
+    // Como `load`, pero entra en pánico si la imagen no se puede cargar
     pub fn new(file_path: &str) -> Texture {
-        let img = ImageReader::open(file_path).unwrap().decode().unwrap();
+        Texture::load(file_path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Carga una imagen de color desde disco (PNG, JPEG, ... según la extensión)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Texture, TextureError> {
+        Texture::load_in(path, ColorSpace::Srgb)
+    }
+
+    // Carga una imagen desde disco interpretando sus valores en `color_space`
+    pub fn load_in<P: AsRef<Path>>(path: P, color_space: ColorSpace) -> Result<Texture, TextureError> {
+        let path = path.as_ref();
+        let img = ImageReader::open(path)
+            .map_err(|source| TextureError::Open {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .decode()
+            .map_err(|source| TextureError::Decode {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        Ok(Texture::from_image_in(img, color_space))
+    }
+
+    pub fn from_image(img: DynamicImage) -> Texture {
+        Texture::from_image_in(img, ColorSpace::Srgb)
+    }
+
+    pub fn from_image_in(img: DynamicImage, color_space: ColorSpace) -> Texture {
         let width = img.width() as usize;
         let height = img.height() as usize;
         let mut texture = Texture {
-            image: img,
+            image: Arc::new(img),
             width,
             height,
-            color_array: vec![Color::black(); width * height],
+            color_array: Arc::new(vec![Color::black(); width * height]),
+            color_space,
+            wrap: WrapMode::Repeat,
+            filter: TextureFilter::Bilinear,
+            mip_levels: Arc::new(Vec::new()),
         };
         texture.load_color_array();
+        texture.build_mipmaps();
+        texture
+    }
+
+    // Tablero de ajedrez de `size` x `size` texels con `cells` casillas por lado,
+    // usado como reemplazo visible de las texturas que faltan
+    pub fn checkerboard(size: usize, cells: usize, first: Color, second: Color) -> Texture {
+        let size = size.max(1);
+        let cell = (size / cells.max(1)).max(1);
+        let to_rgb = |color: Color| {
+            let hex = color.to_hex();
+            Rgb([(hex >> 16) as u8, (hex >> 8) as u8, hex as u8])
+        };
+        let image = RgbImage::from_fn(size as u32, size as u32, |x, y| {
+            if (x as usize / cell + y as usize / cell).is_multiple_of(2) {
+                to_rgb(first)
+            } else {
+                to_rgb(second)
+            }
+        });
+        // Los colores ya son lineales: se guardan sin volver a decodificarlos
+        let mut texture = Texture::from_image_in(DynamicImage::ImageRgb8(image), ColorSpace::Linear);
+        texture.filter = TextureFilter::Nearest;
+        texture
+    }
+
+    // Textura de un solo texel con un color lineal exacto (sin cuantizar a 8 bits)
+    pub fn solid(color: Color) -> Texture {
+        let mut texture = Texture::checkerboard(1, 1, color, color);
+        texture.color_array = Arc::new(vec![color]);
         texture
     }
 
     fn load_color_array(&mut self) {
+        let color_array = Arc::make_mut(&mut self.color_array);
         for x in 0..self.width {
             for y in 0..self.height {
                 let pixel = self.image.get_pixel(x as u32, y as u32).to_rgb();
-                let color = ((pixel[0] as u32) << 16) | ((pixel[1] as u32) << 8) | (pixel[2] as u32);
-                self.color_array[y * self.width + x] = Color::new(pixel[0], pixel[1], pixel[2]); // Updated to Color::new
+                color_array[y * self.width + x] = match self.color_space {
+                    ColorSpace::Srgb => Color::new(pixel[0], pixel[1], pixel[2]),
+                    ColorSpace::Linear => Color::from_linear_u8(pixel[0], pixel[1], pixel[2]),
+                };
+            }
+        }
+    }
+
+    // Genera la cadena de mipmaps promediando bloques de 2x2 texels hasta llegar a 1x1
+    fn build_mipmaps(&mut self) {
+        let mut mip_levels: Vec<MipLevel> = Vec::new();
+        let (mut width, mut height) = (self.width, self.height);
+        while width > 1 || height > 1 {
+            let previous = match mip_levels.last() {
+                Some(level) => (level.width, level.texels.as_slice()),
+                None => (self.width, self.color_array.as_slice()),
+            };
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            let mut texels = Vec::with_capacity(next_width * next_height);
+            for y in 0..next_height {
+                for x in 0..next_width {
+                    // En dimensiones impares el último texel se repite
+                    let x0 = (2 * x).min(width - 1);
+                    let x1 = (2 * x + 1).min(width - 1);
+                    let y0 = (2 * y).min(height - 1);
+                    let y1 = (2 * y + 1).min(height - 1);
+                    let sum = previous.1[y0 * width + x0]
+                        + previous.1[y0 * width + x1]
+                        + previous.1[y1 * width + x0]
+                        + previous.1[y1 * width + x1];
+                    texels.push(sum * 0.25);
+                }
+            }
+            mip_levels.push(MipLevel {
+                width: next_width,
+                height: next_height,
+                texels,
+            });
+            width = next_width;
+            height = next_height;
+        }
+        self.mip_levels = Arc::new(mip_levels);
+    }
+
+    // Número de niveles de detalle, incluyendo la imagen original
+    pub fn mip_count(&self) -> usize {
+        self.mip_levels.len() + 1
+    }
+
+    // (ancho, texels) de un nivel
+    fn level(&self, level: usize) -> (usize, &[Color]) {
+        match level {
+            0 => (self.width, &self.color_array),
+            _ => {
+                let mip = &self.mip_levels[level - 1];
+                (mip.width, &mip.texels)
+            }
+        }
+    }
+
+    // Nivel de detalle para una huella de píxel de `footprint` unidades de textura
+    // (la fracción de la textura que cubre un píxel)
+    pub fn level_for_footprint(&self, footprint: f32) -> f32 {
+        let texels = footprint * self.width.max(self.height) as f32;
+        if texels > 1.0 {
+            texels.log2().min((self.mip_count() - 1) as f32)
+        } else {
+            0.0
+        }
+    }
+
+    // Color en las coordenadas (u, v) del nivel de máximo detalle.
+    // v = 0 es el borde inferior de la imagen.
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        self.sample_level(u, v, 0.0)
+    }
+
+    // Color en (u, v) para un nivel de detalle fraccionario, interpolando entre los
+    // dos mipmaps más cercanos (filtrado trilineal con `TextureFilter::Bilinear`)
+    pub fn sample_level(&self, u: f32, v: f32, level: f32) -> Color {
+        let max_level = (self.mip_count() - 1) as f32;
+        let level = level.clamp(0.0, max_level);
+        let lower = level.floor() as usize;
+        let fraction = level - lower as f32;
+        let color = self.sample_mip(u, v, lower);
+        if fraction > 0.0 {
+            color.lerp(self.sample_mip(u, v, lower + 1), fraction)
+        } else {
+            color
+        }
+    }
+
+    fn sample_mip(&self, u: f32, v: f32, level: usize) -> Color {
+        let (width, texels) = self.level(level);
+        let height = texels.len() / width;
+        // Coordenadas en texels, con los centros en +0.5
+        let x = u * width as f32 - 0.5;
+        let y = (1.0 - v) * height as f32 - 0.5;
+        let texel = |x: i64, y: i64| {
+            let x = wrap(x, width, self.wrap);
+            let y = wrap(y, height, self.wrap);
+            texels[y * width + x]
+        };
+
+        match self.filter {
+            TextureFilter::Nearest => texel(x.round() as i64, y.round() as i64),
+            TextureFilter::Bilinear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+                let bottom = texel(x0, y0 + 1).lerp(texel(x0 + 1, y0 + 1), fx);
+                top.lerp(bottom, fy)
             }
         }
     }
@@ -45,53 +258,137 @@ impl Texture {
             self.color_array[y * self.width + x] // Corrected indexing
         }
     }
-    pub fn new_normal_map(file_path: &str) -> Texture {
-        let img = ImageReader::open(file_path).unwrap().decode().unwrap();
-        let width = img.width() as usize;
-        let height = img.height() as usize;
-        let mut texture = Texture {
-            image: img,
-            width,
-            height,
-            color_array: vec![Color::black(); width * height],  // No necesitas color
-        };
-        texture.load_normal_map();  // Carga la textura del normal map
-        texture
-    }
+}
 
-    // Método que carga un normal map y convierte los valores RGB a un vector normalizado
-    fn load_normal_map(&mut self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let pixel = self.image.get_pixel(x as u32, y as u32).to_rgb();
-                let r = (pixel[0] as f32 / 255.0) * 2.0 - 1.0; // Convertir de 0-255 a -1.0 a 1.0
-                let g = (pixel[1] as f32 / 255.0) * 2.0 - 1.0;
-                let b = (pixel[2] as f32 / 255.0) * 2.0 - 1.0;
+// Error al cargar una textura desde disco
+#[derive(Debug)]
+pub enum TextureError {
+    Open { path: PathBuf, source: io::Error },
+    Decode { path: PathBuf, source: ImageError },
+}
 
-                // Convertir los valores a un vector 3D y normalizarlo
-                let normal = Vec3::new(r, g, b).normalize();
-                self.color_array[y * self.width + x] = Color::from_vec(normal);  // Puedes adaptar `Color` a tu caso
+impl TextureError {
+    pub fn path(&self) -> &Path {
+        match self {
+            TextureError::Open { path, .. } | TextureError::Decode { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Open { path, source } => {
+                write!(f, "no se pudo abrir la textura '{}': {}", path.display(), source)
+            }
+            TextureError::Decode { path, source } => {
+                write!(f, "no se pudo decodificar la textura '{}': {}", path.display(), source)
             }
         }
     }
+}
 
-    // Obtener el valor del normal map en las coordenadas `x, y`
-    pub fn get_normal(&self, x: usize, y: usize) -> Vec3 {
-        if x >= self.width || y >= self.height {
-            Vec3::new(0.0, 0.0, 1.0)  // Vector normal por defecto (hacia arriba)
-        } else {
-            let color = self.color_array[y * self.width + x];
-            Vec3::new(color.r as f32, color.g as f32, color.b as f32)
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextureError::Open { source, .. } => Some(source),
+            TextureError::Decode { source, .. } => Some(source),
         }
     }
 }
 
+// Índice de texel dentro de [0, size) según el modo de repetición
+fn wrap(index: i64, size: usize, mode: WrapMode) -> usize {
+    let size = size as i64;
+    let index = match mode {
+        WrapMode::Repeat => index.rem_euclid(size),
+        WrapMode::Clamp => index.clamp(0, size - 1),
+        WrapMode::Mirror => {
+            let period = index.rem_euclid(2 * size);
+            if period < size {
+                period
+            } else {
+                2 * size - 1 - period
+            }
+        }
+    };
+    index as usize
+}
 
 impl fmt::Debug for Texture {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Texture")
             .field("width", &self.width)
             .field("height", &self.height)
+            .field("wrap", &self.wrap)
+            .field("filter", &self.filter)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(texture: &Texture, color: Color) -> f32 {
+        assert!((color.r - color.g).abs() < 1e-6 && (color.g - color.b).abs() < 1e-6, "{:?} in {:?}", color, texture);
+        color.r
+    }
+
+    #[test]
+    fn wrap_modes_map_out_of_range_indices() {
+        let cases = [
+            (WrapMode::Repeat, [(-1, 3), (-5, 3), (4, 0), (9, 1), (2, 2)]),
+            (WrapMode::Clamp, [(-1, 0), (-5, 0), (4, 3), (9, 3), (2, 2)]),
+            (WrapMode::Mirror, [(-1, 0), (-5, 3), (4, 3), (9, 1), (2, 2)]),
+        ];
+        for (mode, indices) in cases {
+            for (index, expected) in indices {
+                assert_eq!(wrap(index, 4, mode), expected, "{:?} at {}", mode, index);
+            }
+        }
+    }
+
+    #[test]
+    fn bilinear_filter_interpolates_between_texel_centers() {
+        let black = Color::black();
+        let white = Color::from_f32(1.0, 1.0, 1.0);
+        // Dos texels por fila: negro a la izquierda y blanco a la derecha
+        let mut texture = Texture::checkerboard(2, 2, black, white);
+        texture.wrap = WrapMode::Clamp;
+        texture.filter = TextureFilter::Bilinear;
+        let v = 0.75;
+        assert!(grey(&texture, texture.sample(0.25, v)).abs() < 1e-6);
+        assert!((grey(&texture, texture.sample(0.5, v)) - 0.5).abs() < 1e-6);
+        assert!((grey(&texture, texture.sample(0.625, v)) - 0.75).abs() < 1e-6);
+        assert!((grey(&texture, texture.sample(0.75, v)) - 1.0).abs() < 1e-6);
+
+        texture.filter = TextureFilter::Nearest;
+        assert_eq!(grey(&texture, texture.sample(0.45, v)), 0.0);
+        assert_eq!(grey(&texture, texture.sample(0.55, v)), 1.0);
+    }
+
+    #[test]
+    fn mipmaps_average_down_to_one_texel() {
+        let white = Color::from_f32(1.0, 1.0, 1.0);
+        let mut texture = Texture::checkerboard(4, 4, white, Color::black());
+        texture.filter = TextureFilter::Bilinear;
+        assert_eq!(texture.mip_count(), 3);
+        for level in 1..texture.mip_count() {
+            let (width, texels) = texture.level(level);
+            assert_eq!(width, 4 >> level);
+            assert!(texels.iter().all(|texel| (grey(&texture, *texel) - 0.5).abs() < 1e-6));
+        }
+
+        // Un píxel que cubre un texel, media textura y la textura entera
+        assert_eq!(texture.level_for_footprint(0.25), 0.0);
+        assert!((texture.level_for_footprint(0.5) - 1.0).abs() < 1e-6);
+        assert!((texture.level_for_footprint(1.0) - 2.0).abs() < 1e-6);
+        assert_eq!(texture.level_for_footprint(100.0), 2.0);
+
+        // Entre el texel de máximo detalle y el nivel promediado
+        let corner = grey(&texture, texture.sample_level(0.125, 0.875, 0.0));
+        let blended = grey(&texture, texture.sample_level(0.125, 0.875, 0.5));
+        assert!((blended - (corner + 0.5) / 2.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,269 @@
+// src/texture_registry.rs
+
+use crate::color::Color;
+use crate::texture::{ColorSpace, Texture, TextureError, TextureFilter, WrapMode};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Tablero de ajedrez que reemplaza a las texturas que no se pueden cargar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FallbackTexture {
+    pub colors: [Color; 2],
+    pub cells: usize, // Casillas por lado
+}
+
+impl Default for FallbackTexture {
+    fn default() -> Self {
+        FallbackTexture {
+            colors: [Color::new(255, 0, 255), Color::new(0, 0, 0)],
+            cells: 8,
+        }
+    }
+}
+
+impl FallbackTexture {
+    pub fn texture(&self) -> Texture {
+        Texture::checkerboard(64, self.cells, self.colors[0], self.colors[1])
+    }
+}
+
+// Para qué se usa una textura: decide su espacio de color y con qué se reemplaza si falta
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureUsage {
+    Color,     // Textura difusa, en sRGB; se reemplaza por el tablero de ajedrez
+    NormalMap, // Lineal; se reemplaza por una normal plana (0.5, 0.5, 1)
+    Scalar,    // Rugosidad o metalicidad, lineal; se reemplaza por 1 (no cambia el valor del material)
+}
+
+impl TextureUsage {
+    pub fn color_space(self) -> ColorSpace {
+        match self {
+            TextureUsage::Color => ColorSpace::Srgb,
+            TextureUsage::NormalMap | TextureUsage::Scalar => ColorSpace::Linear,
+        }
+    }
+}
+
+// Caché de texturas cargadas desde disco. Cada archivo se lee una sola vez por
+// espacio de color y se comparte con `Arc`; pedirlo con otro modo de repetición
+// o filtro comparte los texels ya decodificados. Con un reemplazo configurado (por
+// defecto), una textura que falta se sustituye por el reemplazo de su uso y se
+// guarda un aviso en lugar de fallar; no se vuelve a buscar en disco hasta que se invalide.
+#[derive(Debug)]
+pub struct TextureRegistry {
+    textures: HashMap<(PathBuf, ColorSpace, WrapMode, TextureFilter), Arc<Texture>>,
+    // Archivos que no se pudieron cargar, para vigilarlos hasta que aparezcan
+    // (mientras tanto se sirve el reemplazo sin repetir el aviso)
+    missing: BTreeSet<PathBuf>,
+    // Tablero de ajedrez junto con la configuración con la que se generó
+    fallback: Option<(FallbackTexture, Arc<Texture>)>,
+    flat_normal: Arc<Texture>,
+    white: Arc<Texture>,
+    // Avisos pendientes de mostrar (texturas que faltan)
+    warnings: Vec<String>,
+    // Archivos pedidos desde la última llamada a `take_requested`
+    requested: BTreeSet<PathBuf>,
+}
+
+impl Default for TextureRegistry {
+    fn default() -> Self {
+        TextureRegistry::new()
+    }
+}
+
+impl TextureRegistry {
+    pub fn new() -> Self {
+        TextureRegistry {
+            textures: HashMap::new(),
+            missing: BTreeSet::new(),
+            fallback: Some((FallbackTexture::default(), Arc::new(FallbackTexture::default().texture()))),
+            flat_normal: Arc::new(Texture::solid(Color::from_f32(0.5, 0.5, 1.0))),
+            white: Arc::new(Texture::solid(Color::from_f32(1.0, 1.0, 1.0))),
+            warnings: Vec::new(),
+            requested: BTreeSet::new(),
+        }
+    }
+
+    // `None` desactiva el reemplazo: los errores de carga se devuelven. El tablero
+    // solo se vuelve a generar si cambia su configuración.
+    pub fn set_fallback(&mut self, fallback: Option<FallbackTexture>) {
+        if self.fallback.as_ref().map(|(current, _)| *current) == fallback {
+            return;
+        }
+        self.fallback = fallback.map(|fallback| (fallback, Arc::new(fallback.texture())));
+    }
+
+    // Reemplazo de las texturas de color que faltan
+    pub fn fallback(&self) -> Option<&Arc<Texture>> {
+        self.fallback.as_ref().map(|(_, texture)| texture)
+    }
+
+    // Reemplazo para una textura con el uso dado (`None` si está desactivado)
+    fn fallback_for(&self, usage: TextureUsage) -> Option<Arc<Texture>> {
+        let checkerboard = self.fallback()?;
+        Some(match usage {
+            TextureUsage::Color => checkerboard.clone(),
+            TextureUsage::NormalMap => self.flat_normal.clone(),
+            TextureUsage::Scalar => self.white.clone(),
+        })
+    }
+
+    // Devuelve y vacía los avisos acumulados desde la última llamada
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // Número de texturas distintas cargadas (sin contar el reemplazo)
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    // Devuelve y vacía los archivos pedidos desde la última llamada, se hayan
+    // podido cargar o no (las texturas que ya no se usan no aparecen)
+    pub fn take_requested(&mut self) -> BTreeSet<PathBuf> {
+        std::mem::take(&mut self.requested)
+    }
+
+    // Olvida un archivo para que se vuelva a leer de disco la próxima vez que se pida
+    pub fn invalidate<P: AsRef<Path>>(&mut self, path: P) {
+        // Un archivo que faltaba puede tener ahora otra ruta canónica
+        self.missing.remove(path.as_ref());
+        let path = canonical(path.as_ref());
+        self.textures.retain(|(loaded, _, _, _), _| *loaded != path);
+        self.missing.remove(&path);
+    }
+
+    pub fn load<P: AsRef<Path>>(&mut self, path: P, usage: TextureUsage) -> Result<Arc<Texture>, TextureError> {
+        self.load_with(path, usage, WrapMode::Repeat, TextureFilter::Bilinear)
+    }
+
+    pub fn load_with<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        usage: TextureUsage,
+        wrap: WrapMode,
+        filter: TextureFilter,
+    ) -> Result<Arc<Texture>, TextureError> {
+        let color_space = usage.color_space();
+        let key = (canonical(path.as_ref()), color_space, wrap, filter);
+        self.requested.insert(key.0.clone());
+        if let Some(texture) = self.textures.get(&key) {
+            return Ok(texture.clone());
+        }
+        if self.missing.contains(&key.0) {
+            if let Some(fallback) = self.fallback_for(usage) {
+                return Ok(fallback);
+            }
+        }
+
+        let decoded = self
+            .textures
+            .iter()
+            .find(|((loaded, space, _, _), _)| *loaded == key.0 && *space == color_space)
+            .map(|(_, texture)| Texture::clone(texture));
+        let mut texture = match decoded {
+            Some(texture) => texture,
+            None => match Texture::load_in(&key.0, color_space) {
+                Ok(texture) => {
+                    self.missing.remove(&key.0);
+                    texture
+                }
+                Err(error) => {
+                    self.missing.insert(key.0.clone());
+                    return match self.fallback_for(usage) {
+                        Some(fallback) => {
+                            self.warnings.push(format!("{}; se usa una textura de reemplazo", error));
+                            Ok(fallback)
+                        }
+                        None => Err(error),
+                    }
+                }
+            },
+        };
+        texture.wrap = wrap;
+        texture.filter = filter;
+        let texture = Arc::new(texture);
+        self.textures.insert(key, texture.clone());
+        Ok(texture)
+    }
+}
+
+// Rutas distintas al mismo archivo comparten la entrada
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tatooine-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn sampler_variants_share_texels() {
+        let path = temp_path("shared.png");
+        RgbImage::from_pixel(4, 4, Rgb([200, 100, 50])).save(&path).unwrap();
+
+        let mut registry = TextureRegistry::new();
+        let repeat = registry.load(&path, TextureUsage::Color).unwrap();
+        let clamp = registry
+            .load_with(&path, TextureUsage::Color, WrapMode::Clamp, TextureFilter::Nearest)
+            .unwrap();
+        assert_eq!((clamp.wrap, clamp.filter), (WrapMode::Clamp, TextureFilter::Nearest));
+        assert!(Arc::ptr_eq(&repeat.color_array, &clamp.color_array));
+        assert!(Arc::ptr_eq(&repeat.image, &clamp.image));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_files_are_not_retried_until_invalidated() {
+        let path = temp_path("missing.png");
+        let _ = std::fs::remove_file(&path);
+
+        let mut registry = TextureRegistry::new();
+        let fallback = registry.fallback().unwrap().clone();
+        assert!(Arc::ptr_eq(&registry.load(&path, TextureUsage::Color).unwrap(), &fallback));
+
+        // Que el archivo aparezca en disco solo se nota cuando el vigilante lo invalida
+        RgbImage::from_pixel(2, 2, Rgb([0, 255, 0])).save(&path).unwrap();
+        assert!(Arc::ptr_eq(&registry.load(&path, TextureUsage::Color).unwrap(), &fallback));
+        registry.invalidate(&path);
+        let loaded = registry.load(&path, TextureUsage::Color).unwrap();
+        assert!(!Arc::ptr_eq(&loaded, &fallback));
+        assert_eq!(loaded.get_color(0, 0), Color::new(0, 255, 0));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_maps_use_neutral_fallbacks_and_warn_once() {
+        let path = temp_path("missing-maps.png");
+        let _ = std::fs::remove_file(&path);
+
+        let mut registry = TextureRegistry::new();
+        let normal = registry.load(&path, TextureUsage::NormalMap).unwrap();
+        assert_eq!(normal.get_color(0, 0), Color::from_f32(0.5, 0.5, 1.0));
+        let scalar = registry.load(&path, TextureUsage::Scalar).unwrap();
+        assert_eq!(scalar.get_color(0, 0), Color::from_f32(1.0, 1.0, 1.0));
+        registry.load(&path, TextureUsage::Color).unwrap();
+        assert_eq!(registry.take_warnings().len(), 1);
+        assert!(registry.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn unchanged_fallback_is_not_rebuilt() {
+        let mut registry = TextureRegistry::new();
+        let before = registry.fallback().unwrap().clone();
+        registry.set_fallback(Some(FallbackTexture::default()));
+        assert!(Arc::ptr_eq(&before, registry.fallback().unwrap()));
+        registry.set_fallback(None);
+        assert!(registry.fallback().is_none());
+    }
+}
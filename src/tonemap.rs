@@ -0,0 +1,128 @@
+// src/tonemap.rs
+
+use crate::color::Color;
+
+// Curva de tono aplicada a la radiancia antes de cuantizar a 8 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    None,     // Recorte directo a [0, 1]
+    Reinhard, // x / (1 + x)
+    Aces,     // Aproximación de Narkowicz de la curva ACES
+    Filmic,   // Curva de Hable (Uncharted 2)
+}
+
+impl ToneMap {
+    // Siguiente operador, para alternar desde el teclado
+    pub fn next(self) -> Self {
+        match self {
+            ToneMap::None => ToneMap::Reinhard,
+            ToneMap::Reinhard => ToneMap::Aces,
+            ToneMap::Aces => ToneMap::Filmic,
+            ToneMap::Filmic => ToneMap::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMap::None => "none",
+            ToneMap::Reinhard => "reinhard",
+            ToneMap::Aces => "aces",
+            ToneMap::Filmic => "filmic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [ToneMap::None, ToneMap::Reinhard, ToneMap::Aces, ToneMap::Filmic]
+            .into_iter()
+            .find(|tone_map| tone_map.name() == name)
+    }
+
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            ToneMap::None => x,
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (x * (a * x + b)) / (x * (c * x + d) + e)
+            }
+            ToneMap::Filmic => {
+                const WHITE_POINT: f32 = 11.2;
+                hable(x * 2.0) / hable(WHITE_POINT)
+            }
+        }
+    }
+}
+
+fn hable(x: f32) -> f32 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+// Codificación sRGB de un valor lineal en [0, 1]
+fn srgb_encode(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Transformación de salida: exposición -> curva de tono -> codificación sRGB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputTransform {
+    pub exposure: f32, // En pasos (EV): la radiancia se multiplica por 2^exposure
+    pub tone_map: ToneMap,
+    pub srgb: bool,
+}
+
+impl Default for OutputTransform {
+    // Sin exposición ni curva de tono, codificando en sRGB: los colores de 8 bits
+    // de la escena (decodificados a lineal al cargarlos) salen tal como se escribieron
+    fn default() -> Self {
+        OutputTransform {
+            exposure: 0.0,
+            tone_map: ToneMap::None,
+            srgb: true,
+        }
+    }
+}
+
+impl OutputTransform {
+    // Convierte radiancia lineal en un color de pantalla en [0, 1]
+    pub fn apply(&self, color: Color) -> Color {
+        let scale = self.exposure.exp2();
+        let map = |c: f32| {
+            let mapped = self.tone_map.apply((c * scale).max(0.0)).clamp(0.0, 1.0);
+            if self.srgb {
+                srgb_encode(mapped)
+            } else {
+                mapped
+            }
+        };
+        Color::from_f32(map(color.r), map(color.g), map(color.b))
+    }
+
+    pub fn encode(&self, color: Color) -> u32 {
+        self.apply(color).to_hex()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_output_keeps_mid_grey() {
+        let hex = OutputTransform::default().encode(Color::new(128, 128, 128));
+        assert_eq!(hex, 0x808080);
+    }
+
+    #[test]
+    fn srgb_output_round_trips_8_bit_colors() {
+        let output = OutputTransform::default();
+        for byte in 0..=255u8 {
+            let hex = output.encode(Color::new(byte, byte, byte));
+            assert_eq!(hex & 0xff, byte as u32, "byte {}", byte);
+        }
+    }
+}
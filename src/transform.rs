@@ -0,0 +1,160 @@
+// src/transform.rs
+
+use crate::bvh::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use nalgebra::{Matrix3, Matrix4, Point3, Rotation3, Vector3 as Vec3};
+use std::sync::Arc;
+
+// Places a primitive in the world with an affine 4x4 transform.
+// Rays are moved into object space with the inverse matrix and hits are moved
+// back, so any primitive can be rotated, scaled (also non-uniformly) and
+// translated. The object is shared through an `Arc`, so the same mesh can be
+// instanced many times without copying its geometry.
+#[derive(Debug, Clone)]
+pub struct Transformed<T> {
+    object: Arc<T>,
+    // Material of this instance when it differs from the shared object's
+    material: Option<Material>,
+    transform: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    normal_matrix: Matrix3<f32>, // Inverse transpose of the linear part
+    scale: f32,                  // Average scale factor, to convert texture densities
+}
+
+impl<T: RayIntersect> Transformed<T> {
+    // None if `transform` is not finite or not invertible
+    pub fn new(object: T, transform: Matrix4<f32>) -> Option<Self> {
+        Transformed::instance(Arc::new(object), transform)
+    }
+
+    // Another placement of an object that may be shared with other instances
+    pub fn instance(object: Arc<T>, transform: Matrix4<f32>) -> Option<Self> {
+        let inverse = invert(&transform)?;
+        Some(Transformed::with_inverse(object, transform, inverse))
+    }
+
+    // Placement with an inverse already computed (see `invert`)
+    pub fn with_inverse(object: Arc<T>, transform: Matrix4<f32>, inverse: Matrix4<f32>) -> Self {
+        let normal_matrix = inverse.fixed_view::<3, 3>(0, 0).transpose();
+        let scale = transform.fixed_view::<3, 3>(0, 0).determinant().abs().cbrt();
+        Transformed {
+            object,
+            material: None,
+            transform,
+            inverse,
+            normal_matrix,
+            scale,
+        }
+    }
+
+    pub fn object(&self) -> &Arc<T> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Matrix4<f32> {
+        &self.transform
+    }
+}
+
+// Inverse of an affine transform, or None if the matrix (or its inverse) has
+// non-finite entries or is singular, e.g. with a scale of 0
+pub fn invert(transform: &Matrix4<f32>) -> Option<Matrix4<f32>> {
+    if !transform.iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    transform
+        .try_inverse()
+        .filter(|inverse| inverse.iter().all(|v| v.is_finite()))
+}
+
+// Translation * rotation (XYZ Euler angles, in degrees) * scale
+pub fn compose(translation: Vec3<f32>, rotation_degrees: Vec3<f32>, scale: Vec3<f32>) -> Matrix4<f32> {
+    let rotation = Rotation3::from_euler_angles(
+        rotation_degrees.x.to_radians(),
+        rotation_degrees.y.to_radians(),
+        rotation_degrees.z.to_radians(),
+    );
+    Matrix4::new_translation(&translation) * rotation.to_homogeneous() * Matrix4::new_nonuniform_scaling(&scale)
+}
+
+impl<T: RayIntersect> RayIntersect for Transformed<T> {
+    fn ray_intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect> {
+        // The direction is not renormalized, so the hit distance `t` is the
+        // same in object and world space
+        let local_origin = self.inverse.transform_point(&Point3::from(*ray_origin)).coords;
+        let local_direction = self.inverse.transform_vector(ray_direction);
+        let mut hit = self.object.ray_intersect(&local_origin, &local_direction)?;
+        if let Some(material) = &self.material {
+            hit.material = material.clone();
+        }
+
+        hit.point = ray_origin + ray_direction * hit.distance;
+        hit.normal = (self.normal_matrix * hit.normal).normalize();
+        hit.uv_scale /= self.scale;
+        // Tangents lie on the surface, so they transform like positions (without translation)
+        hit.tangents = hit
+            .tangents
+            .map(|(tangent, bitangent)| (self.transform.transform_vector(&tangent), self.transform.transform_vector(&bitangent)));
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let local = self.object.bounding_box();
+        if !local.is_finite() {
+            return local;
+        }
+        (0..8).fold(Aabb::empty(), |bounds, corner| {
+            let pick = |axis: usize| {
+                if corner & (1 << axis) == 0 {
+                    local.min[axis]
+                } else {
+                    local.max[axis]
+                }
+            };
+            let point = self
+                .transform
+                .transform_point(&Point3::new(pick(0), pick(1), pick(2)))
+                .coords;
+            bounds.union(&Aabb::new(point, point))
+        })
+    }
+
+    fn material(&self) -> &Material {
+        self.material.as_ref().unwrap_or_else(|| self.object.material())
+    }
+
+    // Changing the material of one instance leaves the others (and the shared
+    // geometry) alone: the first change stores a copy on this instance
+    fn material_mut(&mut self) -> &mut Material {
+        self.material.get_or_insert_with(|| self.object.material().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn instance_materials_do_not_copy_the_shared_object() {
+        let red = Material::new(Color::new(255, 0, 0), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        let blue = Material::new(Color::new(0, 0, 255), [1.0, 0.0, 0.0, 0.0], 0.0, 1.0);
+        let sphere = Arc::new(Sphere::new(Vec3::zeros(), 1.0, red.clone()));
+        let moved = compose(Vec3::x() * 3.0, Vec3::zeros(), Vec3::repeat(1.0));
+        let mut first = Transformed::instance(sphere.clone(), moved).unwrap();
+        let second = Transformed::instance(sphere.clone(), Matrix4::identity()).unwrap();
+
+        *first.material_mut() = blue.clone();
+        assert!(Arc::ptr_eq(first.object(), &sphere) && Arc::ptr_eq(second.object(), &sphere));
+        assert_eq!(first.material(), &blue);
+        assert_eq!(second.material(), &red);
+
+        let hit = |object: &Transformed<Sphere>, x: f32| {
+            object.ray_intersect(&Vec3::new(x, 0.0, 5.0), &-Vec3::z()).unwrap()
+        };
+        assert_eq!(hit(&first, 3.0).material, blue);
+        assert_eq!(hit(&second, 0.0).material, red);
+    }
+}
@@ -0,0 +1,212 @@
+// src/triangle.rs
+
+use crate::bvh::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use nalgebra::{Vector2 as Vec2, Vector3 as Vec3};
+
+const EPSILON: f32 = 1e-7;
+
+// Single triangle with optional per-vertex normals and texture coordinates.
+// Without vertex normals the face normal is used (flat shading).
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    pub vertices: [Vec3<f32>; 3],
+    pub normals: Option<[Vec3<f32>; 3]>,
+    pub uvs: Option<[(f32, f32); 3]>,
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vec3<f32>; 3], material: Material) -> Self {
+        Triangle {
+            vertices,
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3<f32>; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+// Möller–Trumbore ray/triangle test. Returns (t, u, v), where u and v are the
+// barycentric weights of the second and third vertices.
+pub(crate) fn intersect_triangle(
+    vertices: &[Vec3<f32>; 3],
+    ray_origin: &Vec3<f32>,
+    ray_direction: &Vec3<f32>,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = ray_direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    // Ray parallel to the triangle plane (both faces are hit)
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = ray_origin - vertices[0];
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = ray_direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_det;
+    if t > EPSILON {
+        Some((t, u, v))
+    } else {
+        None
+    }
+}
+
+// Interpolates per-vertex attributes with the barycentric weights of a hit
+pub(crate) fn barycentric<T>(values: [T; 3], u: f32, v: f32) -> T
+where
+    T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+    let [a, b, c] = values;
+    a * (1.0 - u - v) + b * u + c * v
+}
+
+// Shading normal at a hit: the interpolated vertex normals, or the face normal
+// following the counter-clockwise winding. It is not flipped towards the ray,
+// so the renderer can tell whether a ray enters or leaves a closed mesh.
+pub(crate) fn shading_normal(vertices: &[Vec3<f32>; 3], normals: Option<[Vec3<f32>; 3]>, u: f32, v: f32) -> Vec3<f32> {
+    match normals {
+        Some(normals) => barycentric(normals, u, v).normalize(),
+        None => (vertices[1] - vertices[0]).cross(&(vertices[2] - vertices[0])).normalize(),
+    }
+}
+
+// Average change of the texture coordinates per world unit over a triangle,
+// from the ratio of its areas in texture space and in world space
+pub(crate) fn uv_scale(vertices: &[Vec3<f32>; 3], uvs: &[Vec2<f32>; 3]) -> f32 {
+    let world_area = (vertices[1] - vertices[0]).cross(&(vertices[2] - vertices[0])).norm();
+    let uv_area = (uvs[1] - uvs[0]).perp(&(uvs[2] - uvs[0])).abs();
+    if world_area > 0.0 {
+        (uv_area / world_area).sqrt()
+    } else {
+        0.0
+    }
+}
+
+// Directions in which u and v grow over a triangle (dP/du, dP/dv), or None if
+// its texture coordinates are degenerate
+pub(crate) fn uv_tangents(vertices: &[Vec3<f32>; 3], uvs: &[Vec2<f32>; 3]) -> Option<(Vec3<f32>, Vec3<f32>)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let duv1 = uvs[1] - uvs[0];
+    let duv2 = uvs[2] - uvs[0];
+    let det = duv1.perp(&duv2);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let tangent = (edge1 * duv2.y - edge2 * duv1.y) / det;
+    let bitangent = (edge2 * duv1.x - edge1 * duv2.x) / det;
+    Some((tangent, bitangent))
+}
+
+pub(crate) fn triangle_bounds(vertices: &[Vec3<f32>; 3]) -> Aabb {
+    Aabb::new(
+        vertices[0].inf(&vertices[1]).inf(&vertices[2]),
+        vertices[0].sup(&vertices[1]).sup(&vertices[2]),
+    )
+}
+
+impl RayIntersect for Triangle {
+    fn ray_intersect(&self, ray_origin: &Vec3<f32>, ray_direction: &Vec3<f32>) -> Option<Intersect> {
+        let (t, u, v) = intersect_triangle(&self.vertices, ray_origin, ray_direction)?;
+        let point = ray_origin + ray_direction * t;
+        let normal = shading_normal(&self.vertices, self.normals, u, v);
+
+        let hit = Intersect::new(point, normal, t, self.material.clone());
+        Some(match self.uvs {
+            Some(uvs) => {
+                let uvs = uvs.map(|(a, b)| Vec2::new(a, b));
+                let uv = barycentric(uvs, u, v);
+                let hit = hit.with_uv((uv.x, uv.y), uv_scale(&self.vertices, &uvs));
+                match uv_tangents(&self.vertices, &uvs) {
+                    Some((tangent, bitangent)) => hit.with_tangents(tangent, bitangent),
+                    None => hit,
+                }
+            }
+            None => hit,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        triangle_bounds(&self.vertices)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter-clockwise seen from +Z
+    const VERTICES: [Vec3<f32>; 3] = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+
+    fn hit(origin: [f32; 3], direction: [f32; 3]) -> Option<(f32, f32, f32)> {
+        intersect_triangle(&VERTICES, &Vec3::from(origin), &Vec3::from(direction))
+    }
+
+    #[test]
+    fn hits_inside_with_barycentric_weights() {
+        let (t, u, v) = hit([0.25, 0.5, 2.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((t - 2.0).abs() < 1e-6);
+        assert!((u - 0.25).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses_outside_parallel_and_behind() {
+        assert!(hit([0.75, 0.75, 1.0], [0.0, 0.0, -1.0]).is_none());
+        assert!(hit([-0.1, 0.5, 1.0], [0.0, 0.0, -1.0]).is_none());
+        assert!(hit([0.25, 0.25, 1.0], [1.0, 0.0, 0.0]).is_none());
+        assert!(hit([0.25, 0.25, 1.0], [0.0, 0.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn edges_and_vertices_count_as_hits() {
+        assert!(hit([0.5, 0.0, 1.0], [0.0, 0.0, -1.0]).is_some());
+        assert!(hit([0.5, 0.5, 1.0], [0.0, 0.0, -1.0]).is_some());
+        assert!(hit([0.0, 1.0, 1.0], [0.0, 0.0, -1.0]).is_some());
+    }
+
+    #[test]
+    fn back_faces_are_hit_without_flipping_the_normal() {
+        let triangle = Triangle::new(VERTICES, Material::black());
+        let hit = triangle
+            .ray_intersect(&Vec3::new(0.25, 0.25, -1.0), &Vec3::new(0.0, 0.0, 1.0))
+            .unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert!((hit.normal - Vec3::z()).norm() < 1e-6);
+    }
+}
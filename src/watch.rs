@@ -0,0 +1,72 @@
+// src/watch.rs
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Vigila una lista de archivos comparando su fecha de modificación en cada
+// consulta (sondeo, sin depender de notificaciones del sistema). Un archivo
+// que no existe cuenta como cambiado cuando aparece, y al revés.
+#[derive(Debug, Clone, Default)]
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    pub fn new<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref().to_path_buf();
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+        FileWatcher { files }
+    }
+
+    // Archivos modificados desde la consulta anterior (o desde `new`)
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_modified_and_removed_files() {
+        let path = std::env::temp_dir().join(format!("tatooine-{}-watched.toml", std::process::id()));
+        std::fs::write(&path, "[camera]").unwrap();
+        let mut watcher = FileWatcher::new([&path]);
+        assert!(watcher.changed().is_empty());
+
+        // Fecha de modificación distinta sin depender de la resolución del reloj
+        let later = modified(&path).unwrap() + Duration::from_secs(10);
+        File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert_eq!(watcher.changed(), vec![path.clone()]);
+        assert!(watcher.changed().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.changed(), vec![path.clone()]);
+        assert!(watcher.changed().is_empty());
+    }
+}